@@ -28,17 +28,31 @@ fn main() {
 
                 println!("gaming!: {:?}", entry.path());
 
-                let layout = BoardLayout::from_fn((15, 15), standard_board_layout);
+                let layout = BoardLayout::standard();
 
                 let players = vec![
                     Player::new("Gamer 1".to_string()),
                     Player::new("Player 2".to_string()),
                 ];
 
-                let mut game = Game::new(players, layout, Language::by_name("english").unwrap());
+                let language = Language::by_name("english").unwrap();
+                let mut game = Game::new(players, layout, language);
 
+                // the files only record the plays, not the racks, so hand every
+                // player exactly the tiles they are about to play
                 let asn = logic::asn::ASN::from_file(entry.path());
-                asn.run(&mut game, true).unwrap();
+                for line in asn.lines {
+                    let player = game.current_player().unwrap();
+                    game.players[player].hand = Hand::from(
+                        line.tiles
+                            .iter()
+                            .map(|t| HandTile::from(*t))
+                            .collect::<Vec<_>>(),
+                    );
+                    logic::asn::ASN { lines: vec![line] }
+                        .run(&mut game, true)
+                        .unwrap();
+                }
             }
         }
     }
@@ -2,11 +2,13 @@ use std::str::FromStr;
 
 use logic::asn::ASN;
 use logic::game::{Game, GameState, Player};
-use logic::language::Language;
+use logic::language::{self, Language};
+use logic::rules::{HintPolicy, Rules};
+use logic::strategy::Bot;
 
-use color_eyre::{Result, eyre};
-use crossterm::event::{self, KeyCode, KeyEvent};
-use logic::{BoardLayout, CrabbleError, standard_board_layout};
+use color_eyre::Result;
+use crossterm::event::{self, KeyCode, KeyEvent, KeyModifiers};
+use logic::{BoardLayout, CrabbleError};
 use ratatui::buffer::Buffer;
 use ratatui::layout::{Constraint, Layout, Position, Rect};
 use ratatui::style::{Color, Style};
@@ -15,6 +17,10 @@ use ratatui::{DefaultTerminal, Frame};
 
 fn main() -> Result<()> {
     color_eyre::install()?;
+    // languages in this directory can be played next to the bundled ones
+    if let Ok(dir) = std::env::var("CRABBLE_LANGUAGES") {
+        language::load_languages(dir)?;
+    }
     let terminal = ratatui::init();
     let app_result = App::new().run(terminal);
     ratatui::restore();
@@ -30,6 +36,10 @@ struct App {
 struct Settings {
     num_players: StringField,
     language: StringField,
+    lexicons: StringField,
+    layout: StringField,
+    bots: StringField,
+    hints: StringField,
     start_button: Button,
     active_box: SettingsActiveBox,
 }
@@ -65,14 +75,18 @@ enum GameTurnActiveBox {
 
 enum State {
     /// Prompts for game settings
-    Setup(Settings),
+    Setup(Box<Settings>),
     /// Current render of game state
-    Gaming(AppGamingState),
+    Gaming(Box<AppGamingState>),
 }
 
 enum SettingsActiveBox {
     NumPlayers,
     Language,
+    Lexicons,
+    Layout,
+    Bots,
+    Hints,
     Start,
 }
 
@@ -92,18 +106,34 @@ struct StringField {
 
 impl GameUI {
     fn new(game: &Game) -> Self {
-        let board = format!("{}", game.board());
-
-        let curr_player = match game.state {
-            GameState::Done => todo!(),
-            GameState::Turn(n, _is_last_round) => n,
+        let (board_label, board) = match game.state {
+            GameState::Done => (
+                "Game over - Final standings".to_owned(),
+                final_standings(game),
+            ),
+            GameState::Turn(n, _is_last_round) => (
+                format!(
+                    "Current Board ({}{}) - Player {}'s turn",
+                    game.language()
+                        .selected_lexicons()
+                        .map(|lexicon| lexicon.name())
+                        .collect::<Vec<_>>()
+                        .join(" + "),
+                    // valid words can be refused when they are missing from a starter list
+                    match game.language().has_complete_lexicon() {
+                        true => "",
+                        false => ", partial word list",
+                    },
+                    n + 1
+                ),
+                format!("{}", game.board()),
+            ),
         };
 
         GameUI {
             active_box: GameTurnActiveBox::Move,
             curr_board: {
-                let mut field =
-                    StringField::new(format!("Current Board - Player {}'s turn", curr_player + 1));
+                let mut field = StringField::new(board_label);
                 field.input = board;
                 field
             },
@@ -121,6 +151,14 @@ impl GameUI {
         }
     }
 
+    /// Shows what the move being typed would score, or why it can't be played
+    fn preview_move(&mut self, game: &Game) {
+        self.curr_move.label = match move_preview(game, &self.curr_move.input) {
+            Some(preview) => format!("Move - {preview}"),
+            None => "Move".to_owned(),
+        };
+    }
+
     fn get_active_box(&mut self) -> Option<&mut StringField> {
         match self.active_box {
             GameTurnActiveBox::Move => Some(&mut self.curr_move),
@@ -129,6 +167,52 @@ impl GameUI {
     }
 }
 
+/// Renders the result screen, one line per player
+fn final_standings(game: &Game) -> String {
+    let mut res = String::new();
+
+    for standing in game.final_standings().unwrap_or_default() {
+        res.push_str(&format!(
+            "{}. {}: {} ({} played, -{} left on rack, +{} for going out)\n",
+            standing.rank,
+            standing.name,
+            standing.final_score,
+            standing.play_score,
+            standing.leftover_penalty,
+            standing.going_out_bonus,
+        ));
+    }
+    res
+}
+
+/// What the move in `input` would score on the current board, word by word. Returns `None` while
+/// the input isn't a complete move yet.
+fn move_preview(game: &Game, input: &str) -> Option<String> {
+    let asn = ASN::from_str(input).ok()?;
+    let [line] = asn.lines.as_slice() else {
+        return None;
+    };
+
+    let mut game = game.without_undo_history();
+    let breakdown = match line.place(&mut game).and_then(|()| game.preview_turn()) {
+        Ok(breakdown) => breakdown,
+        Err(e) => return Some(e.to_string()),
+    };
+
+    let mut parts: Vec<String> = breakdown
+        .words()
+        .map(|word| format!("{} {}", word.word, word.total()))
+        .collect();
+    if let Some(bonus) = breakdown.bingo_bonus {
+        parts.push(format!("bingo {bonus}"));
+    }
+    Some(format!(
+        "{} = {} points",
+        parts.join(" + "),
+        breakdown.total()
+    ))
+}
+
 impl StringField {
     fn new(label: String) -> Self {
         StringField {
@@ -192,10 +276,6 @@ impl StringField {
     fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
         new_cursor_pos.clamp(0, self.input.chars().count())
     }
-
-    const fn reset_cursor(&mut self) {
-        self.character_index = 0;
-    }
 }
 
 impl Widget for &Button {
@@ -235,7 +315,28 @@ impl Settings {
     fn new() -> Self {
         Settings {
             num_players: StringField::new("How many players are there?".to_owned()),
-            language: StringField::new("What language would you like to play in?".to_owned()),
+            language: StringField::new(format!(
+                "What language would you like to play in? ({})",
+                language::available()
+                    .iter()
+                    .map(|entry| format!("{} - {}", entry.name, entry.display_name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            lexicons: StringField::new(
+                "Which lexicons? (empty for the standard one, combine several with +)".to_owned(),
+            ),
+            layout: StringField::new(format!(
+                "Which board? (empty for the standard one, {} or a layout file)",
+                BoardLayout::available().join(", ")
+            )),
+            bots: StringField::new(format!(
+                "Which players does the computer play? (like 2=greedy 3=strong, levels: {})",
+                Bot::ALL.map(|bot| bot.name()).join(", ")
+            )),
+            hints: StringField::new(
+                "Hints? (empty for unlimited, off, or how many per player)".to_owned(),
+            ),
             start_button: Button::new("Start Game!".to_owned()),
             active_box: SettingsActiveBox::NumPlayers,
         }
@@ -245,48 +346,55 @@ impl Settings {
         match self.active_box {
             SettingsActiveBox::NumPlayers => Some(&mut self.num_players),
             SettingsActiveBox::Language => Some(&mut self.language),
+            SettingsActiveBox::Lexicons => Some(&mut self.lexicons),
+            SettingsActiveBox::Layout => Some(&mut self.layout),
+            SettingsActiveBox::Bots => Some(&mut self.bots),
+            SettingsActiveBox::Hints => Some(&mut self.hints),
             SettingsActiveBox::Start => None,
         }
     }
 
     fn select_next_box(&mut self) {
-        match self.active_box {
-            SettingsActiveBox::NumPlayers => {
-                self.active_box = SettingsActiveBox::Language;
-                self.num_players.selected = false;
-                self.language.selected = true;
-                self.start_button.selected = false;
-            }
-            SettingsActiveBox::Language => {
-                self.active_box = SettingsActiveBox::Start;
-                self.num_players.selected = false;
-                self.language.selected = false;
-                self.start_button.selected = true;
-            }
-            SettingsActiveBox::Start => {
-                self.active_box = SettingsActiveBox::NumPlayers;
-                self.num_players.selected = true;
-                self.language.selected = false;
-                self.start_button.selected = false;
-            }
-        }
+        self.active_box = match self.active_box {
+            SettingsActiveBox::NumPlayers => SettingsActiveBox::Language,
+            SettingsActiveBox::Language => SettingsActiveBox::Lexicons,
+            SettingsActiveBox::Lexicons => SettingsActiveBox::Layout,
+            SettingsActiveBox::Layout => SettingsActiveBox::Bots,
+            SettingsActiveBox::Bots => SettingsActiveBox::Hints,
+            SettingsActiveBox::Hints => SettingsActiveBox::Start,
+            SettingsActiveBox::Start => SettingsActiveBox::NumPlayers,
+        };
+        self.num_players.selected = matches!(self.active_box, SettingsActiveBox::NumPlayers);
+        self.language.selected = matches!(self.active_box, SettingsActiveBox::Language);
+        self.lexicons.selected = matches!(self.active_box, SettingsActiveBox::Lexicons);
+        self.layout.selected = matches!(self.active_box, SettingsActiveBox::Layout);
+        self.bots.selected = matches!(self.active_box, SettingsActiveBox::Bots);
+        self.hints.selected = matches!(self.active_box, SettingsActiveBox::Hints);
+        self.start_button.selected = matches!(self.active_box, SettingsActiveBox::Start);
     }
 
     fn on_key_press(&mut self, event: KeyEvent) -> Option<State> {
         match event.code {
-            KeyCode::Enter => match self.active_box {
-                SettingsActiveBox::Start => match self.start_game() {
-                    Ok((game, ui)) => return Some(State::Gaming(AppGamingState { game, ui })),
-                    Err(e) => self
-                        .start_button
-                        .text
-                        .push_str(&format!(" - {}", e.to_string())),
-                },
-                _ => (),
-            },
+            KeyCode::Enter => {
+                if let SettingsActiveBox::Start = self.active_box {
+                    match self.start_game() {
+                        Ok((game, ui)) => {
+                            let mut gaming = AppGamingState { game, ui };
+                            gaming.play_bots();
+                            return Some(State::Gaming(Box::new(gaming)));
+                        }
+                        Err(e) => self.start_button.text.push_str(&format!(" - {e}")),
+                    }
+                }
+            }
             KeyCode::Tab => self.select_next_box(),
             _ => match self.active_box {
-                SettingsActiveBox::NumPlayers | SettingsActiveBox::Language => {
+                SettingsActiveBox::NumPlayers
+                | SettingsActiveBox::Language
+                | SettingsActiveBox::Lexicons
+                | SettingsActiveBox::Layout
+                | SettingsActiveBox::Bots
+                | SettingsActiveBox::Hints => {
                     let active_box = self.get_active_input_field().unwrap();
 
                     match event.code {
@@ -305,16 +413,31 @@ impl Settings {
     }
 
     fn render(&self, frame: &mut Frame) {
-        let [num_players_area, language_area, start_area] =
-            Layout::vertical(Constraint::from_lengths([3, 3, 1])).areas(frame.area());
+        let [
+            num_players_area,
+            language_area,
+            lexicons_area,
+            layout_area,
+            bots_area,
+            hints_area,
+            start_area,
+        ] = Layout::vertical(Constraint::from_lengths([3, 3, 3, 3, 3, 3, 1])).areas(frame.area());
 
         frame.render_widget(&self.num_players, num_players_area);
         frame.render_widget(&self.language, language_area);
+        frame.render_widget(&self.lexicons, lexicons_area);
+        frame.render_widget(&self.layout, layout_area);
+        frame.render_widget(&self.bots, bots_area);
+        frame.render_widget(&self.hints, hints_area);
 
         frame.render_widget(&self.start_button, start_area);
 
         let (active_area, active_offset) = match self.active_box {
             SettingsActiveBox::Language => (language_area, self.language.character_index),
+            SettingsActiveBox::Lexicons => (lexicons_area, self.lexicons.character_index),
+            SettingsActiveBox::Layout => (layout_area, self.layout.character_index),
+            SettingsActiveBox::Bots => (bots_area, self.bots.character_index),
+            SettingsActiveBox::Hints => (hints_area, self.hints.character_index),
             SettingsActiveBox::NumPlayers => (num_players_area, self.num_players.character_index),
             SettingsActiveBox::Start => (start_area, 0),
         };
@@ -331,25 +454,126 @@ impl Settings {
             .input
             .parse::<u32>()
             .map_err(|_| CrabbleError::InvalidNumberPlayers)?;
-        let language = Language::by_name(&self.language.input).map_err(|e| e)?;
+        let mut language = Language::by_name(&self.language.input)?;
+        if !self.lexicons.input.trim().is_empty() {
+            let lexicons: Vec<_> = self.lexicons.input.split('+').map(str::trim).collect();
+            language.select_lexicons(&lexicons)?;
+        }
+
+        // seats are written as `<player number>=<level>`
+        let mut bots = Vec::new();
+        for seat in self.bots.input.split_whitespace() {
+            let (number, bot) = seat
+                .split_once('=')
+                .ok_or_else(|| CrabbleError::UnknownBot(seat.to_owned()))?;
+            let number = number
+                .parse::<u32>()
+                .map_err(|_| CrabbleError::InvalidNumberPlayers)?;
+            bots.push((number, bot.parse::<Bot>()?));
+        }
 
         let mut players = Vec::new();
-        for i in 0..num_players {
-            let player = Player::new(format!("player {}", i));
+        // seats are numbered from 1, the same as the names of the players
+        for number in 1..=num_players {
+            let player = match bots.iter().find(|(seat, _)| *seat == number) {
+                Some((_, bot)) => Player::computer(format!("player {number} ({bot})"), *bot),
+                None => Player::new(format!("player {number}")),
+            };
             players.push(player);
         }
 
-        let layout = BoardLayout::from_fn((15, 15), standard_board_layout);
+        let layout = match self.layout.input.trim() {
+            "" => BoardLayout::standard(),
+            layout => BoardLayout::find(layout)?,
+        };
+
+        let hints = match self.hints.input.trim() {
+            "" => HintPolicy::Unlimited,
+            hints => hints.parse()?,
+        };
+        let rules = Rules {
+            hints,
+            ..Rules::default()
+        };
 
-        let game = Game::new(players, layout, language);
+        let game = Game::with_rules(players, layout, language, rules);
         let ui = GameUI::new(&game);
         Ok((game, ui))
     }
 }
 
 impl AppGamingState {
+    /// Lets the computer players take their turns until it's a person's turn again
+    fn play_bots(&mut self) {
+        let AppGamingState { game, ui } = self;
+
+        while let Some(player) = game.current_player() {
+            if game.players[player].bot.is_none() {
+                break;
+            }
+            if let Err(e) = game.play_bot_turn() {
+                *ui = GameUI::new(game);
+                ui.submit.text = format!("Submit Move - {e}");
+                return;
+            }
+        }
+        *ui = GameUI::new(game);
+    }
+
+    /// Whether the current turn is taken by a computer player
+    fn is_bot_turn(&self) -> bool {
+        self.game
+            .current_player()
+            .is_some_and(|player| self.game.players[player].bot.is_some())
+    }
+
     fn on_key_press(&mut self, event: KeyEvent) {
+        // ctrl-t fills in the best move
+        if event.modifiers.contains(KeyModifiers::CONTROL) && event.code == KeyCode::Char('t') {
+            let ui = &mut self.ui;
+            match self.game.hints(1) {
+                Ok(hints) => match hints.first() {
+                    Some(hint) => {
+                        ui.curr_move.input = hint.asn.clone();
+                        ui.curr_move.character_index = hint.asn.chars().count();
+                        ui.preview_move(&self.game);
+                        ui.submit.text = format!(
+                            "Submit Move - hint: {} for {} points",
+                            hint.words.join(", "),
+                            hint.score
+                        );
+                    }
+                    None => ui.submit.text = "Submit Move - no moves found".to_owned(),
+                },
+                Err(e) => ui.submit.text = format!("Submit Move - {e}"),
+            }
+            return;
+        }
+
+        // ctrl-z and ctrl-y step back and forward through the committed turns, skipping over the
+        // turns of computer players
+        if event.modifiers.contains(KeyModifiers::CONTROL) {
+            let step = match event.code {
+                KeyCode::Char('z') => Game::undo,
+                KeyCode::Char('y') => Game::redo,
+                _ => return,
+            };
+            let mut res = step(&mut self.game);
+            while res.is_ok() && self.is_bot_turn() {
+                res = step(&mut self.game);
+            }
+            match res {
+                Ok(()) => self.ui = GameUI::new(&self.game),
+                Err(e) => self.ui.submit.text = format!("Submit Move - {e}"),
+            }
+            if self.is_bot_turn() {
+                self.play_bots();
+            }
+            return;
+        }
+
         let AppGamingState { game, ui } = self;
+        let mut played = false;
 
         match event.code {
             KeyCode::Enter => match ui.active_box {
@@ -359,10 +583,14 @@ impl AppGamingState {
                     ui.submit.selected = true;
                 }
                 GameTurnActiveBox::Submit => {
-                    let asn = ASN::from_str(&ui.curr_move.input).unwrap();
-                    // `asn.run`` implicitly calls `end_turn`
-                    asn.run(game, false).unwrap();
-                    *ui = GameUI::new(game);
+                    match ASN::from_str(&ui.curr_move.input) {
+                        // `asn.run`` implicitly calls `end_turn`
+                        Ok(asn) => match asn.run(game, false) {
+                            Ok(()) => played = true,
+                            Err(e) => ui.submit.text = format!("Submit Move - {e}"),
+                        },
+                        Err(e) => ui.submit.text = format!("Submit Move - {e}"),
+                    }
                 }
             },
             KeyCode::Tab => match ui.active_box {
@@ -377,23 +605,32 @@ impl AppGamingState {
                     ui.submit.selected = false;
                 }
             },
-            _ => match ui.active_box {
-                GameTurnActiveBox::Move => match event.code {
-                    KeyCode::Char(c) => ui.curr_move.enter_char(c),
-                    KeyCode::Backspace => ui.curr_move.delete_char(),
-                    KeyCode::Left => ui.curr_move.move_cursor_left(),
-                    KeyCode::Right => ui.curr_move.move_cursor_right(),
-                    _ => (),
-                },
-                GameTurnActiveBox::Submit => (),
-            },
-            _ => {}
+            _ => {
+                if let Some(field) = ui.get_active_box() {
+                    match event.code {
+                        KeyCode::Char(c) => field.enter_char(c),
+                        KeyCode::Backspace => field.delete_char(),
+                        KeyCode::Left => field.move_cursor_left(),
+                        KeyCode::Right => field.move_cursor_right(),
+                        _ => (),
+                    }
+                    if matches!(event.code, KeyCode::Char(_) | KeyCode::Backspace) {
+                        ui.preview_move(game);
+                    }
+                }
+            }
+        }
+
+        if played {
+            self.play_bots();
         }
     }
 
     fn render(&self, frame: &mut Frame) {
+        // the board is as high as its rows, plus the border
+        let board_height = self.ui.curr_board.input.lines().count() as u16 + 2;
         let [cur_board, cur_hand, cur_move, button] =
-            Layout::vertical(Constraint::from_lengths([17, 3, 3, 1])).areas(frame.area());
+            Layout::vertical(Constraint::from_lengths([board_height, 3, 3, 1])).areas(frame.area());
 
         frame.render_widget(&self.ui.curr_board, cur_board);
         frame.render_widget(&self.ui.curr_hand, cur_hand);
@@ -405,7 +642,7 @@ impl AppGamingState {
 impl App {
     fn new() -> Self {
         Self {
-            state: State::Setup(Settings::new()),
+            state: State::Setup(Box::new(Settings::new())),
         }
     }
 
@@ -418,10 +655,11 @@ impl App {
 
     fn on_key_press(&mut self, event: KeyEvent) {
         match &mut self.state {
-            State::Setup(settings) => match settings.on_key_press(event) {
-                Some(state) => self.state = state,
-                None => (),
-            },
+            State::Setup(settings) => {
+                if let Some(state) = settings.on_key_press(event) {
+                    self.state = state
+                }
+            }
             State::Gaming(gaming) => gaming.on_key_press(event),
         }
     }
@@ -0,0 +1,82 @@
+//! Turns the bundled word lists, and their other editions, into FSTs at compile time, so games
+//! don't have to sort and index hundreds of thousands of words at startup. `language.rs` includes
+//! the results. Like `Language::from_dir`, it leaves out the words that the alphabet of the
+//! language can't spell.
+
+use std::{env, fs, path::Path};
+
+#[allow(dead_code)]
+#[path = "src/normalize.rs"]
+mod normalize;
+
+fn main() {
+    let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("../data");
+    let out = env::var("OUT_DIR").unwrap();
+    println!("cargo::rerun-if-changed={}", data.display());
+
+    for entry in fs::read_dir(&data).unwrap() {
+        let dir = entry.unwrap().path();
+        if !dir.is_dir() {
+            continue;
+        }
+        let name = dir.file_name().unwrap().to_str().unwrap();
+
+        // the `fold` rules and the `alphabet` of the metadata, see `Metadata`
+        let metadata_file = dir.join("metadata.txt");
+        println!("cargo::rerun-if-changed={}", metadata_file.display());
+        let metadata = fs::read_to_string(metadata_file).unwrap_or_default();
+        let setting = |name: &str| {
+            metadata
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .find(|(key, _)| key.trim() == name)
+                .map(|(_, value)| value.trim().to_string())
+        };
+        let folding = setting("fold")
+            .map(|rules| normalize::parse_folding(&rules).unwrap())
+            .unwrap_or_default();
+
+        // without an alphabet, the letters of the tiles are, like in `Language::from_dir`
+        let letters_file = dir.join("letters.csv");
+        println!("cargo::rerun-if-changed={}", letters_file.display());
+        let alphabet = match setting("alphabet") {
+            Some(alphabet) => normalize::parse_alphabet(&alphabet),
+            None => fs::read_to_string(letters_file)
+                .unwrap()
+                .lines()
+                .skip(1)
+                .filter_map(|line| line.split(',').next())
+                .filter(|letter| !letter.is_empty() && *letter != " ")
+                .map(normalize::normalize)
+                .collect(),
+        };
+
+        // `words.txt` is the standard lexicon, `words-<edition>.txt` the other editions
+        for file in fs::read_dir(&dir).unwrap() {
+            let words = file.unwrap().path();
+            let file = words.file_name().unwrap().to_str().unwrap();
+            let out_file = match file.strip_suffix(".txt") {
+                Some("words") => format!("{name}.fst"),
+                Some(file) => match file.strip_prefix("words-") {
+                    Some(edition) => format!("{name}-{edition}.fst"),
+                    None => continue,
+                },
+                None => continue,
+            };
+            println!("cargo::rerun-if-changed={}", words.display());
+
+            // the same normalization as `Language::from_dir`
+            let text = fs::read_to_string(&words).unwrap();
+            let mut words: Vec<String> = text
+                .lines()
+                .map(|word| normalize::fold(word.trim(), &folding))
+                .filter(|word| !word.is_empty() && normalize::can_spell(word, &alphabet))
+                .collect();
+            words.sort_unstable();
+            words.dedup();
+
+            let set = fst::Set::from_iter(words).unwrap();
+            fs::write(Path::new(&out).join(out_file), set.as_fst().as_bytes()).unwrap();
+        }
+    }
+}
@@ -1,8 +1,15 @@
-use std::{io::Read, path::PathBuf, str::FromStr};
+use std::{fmt::Display, io::Read, path::PathBuf, str::FromStr};
+
+use thiserror::Error;
+use unicode_normalization::UnicodeNormalization;
 
 use crate::game::Game;
+use crate::movegen::Move;
+
+use crate::{Coordinate, CrabbleError, Direction, Letter, Tile};
 
-use crate::{Coordinate, CrabbleError, Direction, Tile};
+/// Coordinates are written as single digits in this base, so boards can be up to 36 squares wide
+pub const COORDINATE_RADIX: u32 = 36;
 
 pub struct ASN {
     pub lines: Vec<ASNLine>,
@@ -19,21 +26,14 @@ impl ASN {
         ASN::from_str(&s).unwrap()
     }
 
+    /// Plays every line as a turn. If a line can't be played, its tiles are taken back off the
+    /// board before the error is returned, so the game is left at the start of that turn.
     pub fn run(self, game: &mut Game, print_board: bool) -> Result<(), CrabbleError> {
         for line in self.lines {
-            let mut coord = line.coord;
-
-            for tile in line.tiles {
-                game.place_tile(tile, coord)?;
-
-                while game.get_tile(coord).is_some() {
-                    coord += line.dir.to_offset();
-                }
+            if let Err(e) = line.play(game, print_board) {
+                game.recall_all();
+                return Err(e);
             }
-            if print_board {
-                println!("{game}");
-            }
-            game.end_turn()?;
         }
 
         Ok(())
@@ -46,12 +46,80 @@ pub struct ASNLine {
     pub tiles: Vec<Tile>,
 }
 
-#[derive(Debug)]
+impl ASNLine {
+    /// Places the tiles of the line as provisional tiles, without ending the turn
+    pub fn place(&self, game: &mut Game) -> Result<(), CrabbleError> {
+        let mut coord = self.coord;
+
+        for tile in &self.tiles {
+            game.place_tile(*tile, coord)?;
+
+            while game.get_tile(coord).is_some() {
+                coord += self.dir.to_offset();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn play(self, game: &mut Game, print_board: bool) -> Result<(), CrabbleError> {
+        self.place(game)?;
+        if print_board {
+            println!("{game}");
+        }
+        game.end_turn()?;
+
+        Ok(())
+    }
+}
+
+/// Writes the line the way `ASN::from_str` reads it
+impl Display for ASNLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digit = |n: isize| char::from_digit(n as u32, COORDINATE_RADIX).unwrap_or('?');
+        let dir = match self.dir {
+            Direction::Horizontal => 'h',
+            Direction::Vertical => 'v',
+        };
+        write!(f, "{}{}{dir}", digit(self.coord.x), digit(self.coord.y))?;
+
+        for tile in &self.tiles {
+            if tile.is_joker {
+                write!(f, "*")?;
+            }
+            match tile.tile.is_multi_char() {
+                true => write!(f, "({})", tile.tile)?,
+                false => write!(f, "{}", tile.tile)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<&Move> for ASNLine {
+    fn from(play: &Move) -> Self {
+        let mut tiles = play.tiles.clone();
+        tiles.sort_by_key(|(coord, _)| (coord.x, coord.y));
+
+        ASNLine {
+            coord: tiles[0].0,
+            dir: play.dir,
+            tiles: tiles.into_iter().map(|(_, tile)| tile).collect(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
 pub enum ASNError {
+    #[error("Invalid coordinate, expected two digits")]
     InvalidCoord,
+    #[error("Invalid direction, expected h or v")]
     InvalidDirection,
+    #[error("Invalid tile")]
     InvalidTileCharacter,
+    #[error("A joker should be followed by the letter it stands for")]
     InvalidJoker,
+    #[error("The play ends where a tile is expected")]
     UnexpendedPlayEnd,
 }
 
@@ -60,17 +128,18 @@ impl FromStr for ASN {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut asn_lines = vec![];
+        // a letter with an accent might be written as two characters, compose them into one
+        let s: String = s.nfc().collect();
         let mut chars = s.chars();
 
         while let Some(c) = chars.next() {
-            let x =
-                isize::from_str_radix(&c.to_string(), 15).map_err(|_| ASNError::InvalidCoord)?;
-            let y = match chars.next() {
-                Some(c) => {
-                    isize::from_str_radix(&c.to_string(), 15).map_err(|_| ASNError::InvalidCoord)?
-                }
-                _ => return Err(ASNError::InvalidCoord),
+            let digit = |c: char| {
+                c.to_digit(COORDINATE_RADIX)
+                    .map(|digit| digit as isize)
+                    .ok_or(ASNError::InvalidCoord)
             };
+            let x = digit(c)?;
+            let y = digit(chars.next().ok_or(ASNError::InvalidCoord)?)?;
 
             let dir = match chars.next() {
                 Some('v') => Direction::Vertical,
@@ -98,9 +167,25 @@ impl FromStr for ASN {
                         return Err(ASNError::UnexpendedPlayEnd)
                     }
 
+                    // tiles with more than one letter are written between parentheses
+                    (_, Some('(')) => {
+                        let mut symbol = String::new();
+                        loop {
+                            match chars.next() {
+                                Some(')') => break,
+                                Some(c) if !c.is_whitespace() => symbol.push(c),
+                                _ => return Err(ASNError::InvalidTileCharacter),
+                            }
+                        }
+                        tiles.push(Tile {
+                            tile: Letter::new(&symbol).ok_or(ASNError::InvalidTileCharacter)?,
+                            is_joker: matches!(state, JokerTile),
+                        });
+                        state = CanEnd;
+                    }
                     (_, Some(c)) if c.is_alphabetic() => {
                         tiles.push(Tile {
-                            tile: c,
+                            tile: c.into(),
                             is_joker: matches!(state, JokerTile),
                         });
                         state = CanEnd;
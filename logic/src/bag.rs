@@ -1,9 +1,10 @@
-#[cfg(test)]
-use crate::language::Language;
 use crate::{language::Distribution, HandTile};
-use rand::{seq::SliceRandom, thread_rng, Rng};
+#[cfg(test)]
+use crate::{language::Language, Letter};
+use rand::{seq::SliceRandom, Rng};
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bag(Vec<HandTile>);
 
 impl Bag {
@@ -21,19 +22,17 @@ impl Bag {
         Self(vec)
     }
 
-    pub fn shuffle(&mut self) {
-        let mut rng = thread_rng();
-        self.0.shuffle(&mut rng);
+    pub fn shuffle(&mut self, rng: &mut impl Rng) {
+        self.0.shuffle(rng);
     }
 
     pub fn take(&mut self) -> Option<HandTile> {
         self.0.pop()
     }
 
-    pub fn put(&mut self, tile: HandTile) {
+    pub fn put(&mut self, tile: HandTile, rng: &mut impl Rng) {
         self.0.push(tile);
 
-        let mut rng = thread_rng();
         let idx = rng.gen_range(0..self.0.len());
 
         let final_idx = self.0.len() - 1;
@@ -43,6 +42,10 @@ impl Bag {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
 }
 
 #[test]
@@ -52,13 +55,13 @@ fn test_english() {
 
     let mut number_of_e = 0;
     while !bag.is_empty() {
-        if let Some(HandTile::Letter('e')) = bag.take() {
+        if bag.take() == Some(HandTile::Letter('e'.into())) {
             number_of_e += 1;
         }
     }
 
     assert_eq!(number_of_e, 12);
-    assert_eq!(lang.values.get(HandTile::Letter('q')), 10);
+    assert_eq!(lang.values.get(HandTile::Letter('q'.into())), Ok(10));
 }
 
 #[test]
@@ -68,11 +71,92 @@ fn test_dutch() {
 
     let mut number_of_e = 0;
     while !bag.is_empty() {
-        if let Some(HandTile::Letter('e')) = bag.take() {
+        if bag.take() == Some(HandTile::Letter('e'.into())) {
             number_of_e += 1;
         }
     }
 
     assert_eq!(number_of_e, 18);
-    assert_eq!(lang.values.get(HandTile::Letter('y')), 8);
+    assert_eq!(lang.values.get(HandTile::Letter('y'.into())), Ok(8));
+
+    let ij = Letter::new("ij").unwrap();
+    assert_eq!(lang.values.get(HandTile::Letter(ij)), Ok(4));
+    assert_eq!(lang.letter("IJ"), Ok(ij));
+    assert_eq!(lang.letter("Ĳ"), Ok(ij));
+}
+
+#[test]
+fn test_german() {
+    let lang = Language::by_name("german").unwrap();
+    let mut bag = Bag::full(&lang.distribution);
+    assert_eq!(bag.len(), 102);
+
+    let mut number_of_umlauts = 0;
+    while let Some(tile) = bag.take() {
+        if "äöü".contains(&tile.to_string()) {
+            number_of_umlauts += 1;
+        }
+    }
+
+    assert_eq!(number_of_umlauts, 3);
+    assert_eq!(lang.values.get(HandTile::Letter('ö'.into())), Ok(8));
+    assert!(lang.values.get(HandTile::Letter('ß'.into())).is_err());
+}
+
+#[test]
+fn test_french() {
+    let lang = Language::by_name("french").unwrap();
+    let mut bag = Bag::full(&lang.distribution);
+    assert_eq!(bag.len(), 102);
+
+    let mut number_of_e = 0;
+    while !bag.is_empty() {
+        if bag.take() == Some(HandTile::Letter('e'.into())) {
+            number_of_e += 1;
+        }
+    }
+
+    assert_eq!(number_of_e, 15);
+    assert_eq!(lang.values.get(HandTile::Letter('k'.into())), Ok(10));
+    assert_eq!(lang.letter("É"), Ok('e'.into()));
+}
+
+#[test]
+fn test_spanish() {
+    let lang = Language::by_name("spanish").unwrap();
+    let mut bag = Bag::full(&lang.distribution);
+    assert_eq!(bag.len(), 100);
+
+    let mut number_of_digraphs = 0;
+    while let Some(tile) = bag.take() {
+        if let HandTile::Letter(letter) = tile {
+            if letter.is_multi_char() {
+                number_of_digraphs += 1;
+            }
+        }
+    }
+
+    assert_eq!(number_of_digraphs, 3);
+    let ll = HandTile::Letter(Letter::new("ll").unwrap());
+    assert_eq!(lang.values.get(ll), Ok(8));
+    assert_eq!(lang.values.get(HandTile::Letter('ñ'.into())), Ok(8));
+    assert_eq!(lang.letter("CH"), Ok(Letter::new("ch").unwrap()));
+}
+
+#[test]
+fn test_italian() {
+    let lang = Language::by_name("italian").unwrap();
+    let mut bag = Bag::full(&lang.distribution);
+    assert_eq!(bag.len(), 120);
+
+    let mut number_of_o = 0;
+    while !bag.is_empty() {
+        if bag.take() == Some(HandTile::Letter('o'.into())) {
+            number_of_o += 1;
+        }
+    }
+
+    assert_eq!(number_of_o, 15);
+    assert_eq!(lang.values.get(HandTile::Letter('q'.into())), Ok(10));
+    assert!(lang.values.get(HandTile::Letter('x'.into())).is_err());
 }
@@ -0,0 +1,282 @@
+use std::borrow::Cow;
+
+/// A set of words that can be walked letter by letter, like a trie. Nodes stand for the prefixes
+/// of the words, starting with the empty prefix at `root`.
+pub trait Dictionary {
+    type Node: Clone;
+
+    /// The node of the empty prefix
+    fn root(&self) -> Self::Node;
+
+    /// Whether the prefix of `node` is a whole word
+    fn is_word(&self, node: &Self::Node) -> bool;
+
+    /// The letters that can follow the prefix of `node`, in order
+    fn children(&self, node: &Self::Node) -> Vec<(char, Self::Node)>;
+
+    /// The node reached by adding `letter` to the prefix of `node`, if any word continues that way
+    fn child(&self, node: &Self::Node, letter: char) -> Option<Self::Node> {
+        self.children(node)
+            .into_iter()
+            .find(|(c, _)| *c == letter)
+            .map(|(_, child)| child)
+    }
+
+    /// The node reached by adding every letter of `letters` to the prefix of `node`, like for a
+    /// tile with more than one letter
+    fn follow(&self, node: &Self::Node, letters: &str) -> Option<Self::Node> {
+        letters
+            .chars()
+            .try_fold(node.clone(), |node, letter| self.child(&node, letter))
+    }
+
+    /// The node of `prefix`, if any word starts with it
+    fn walk(&self, prefix: &str) -> Option<Self::Node> {
+        self.follow(&self.root(), prefix)
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.walk(word).is_some_and(|node| self.is_word(&node))
+    }
+}
+
+/// The simple backend: a sorted list of words that is binary searched
+#[derive(Clone, Debug, Default)]
+pub struct SortedWords(Vec<String>);
+
+/// A node of `SortedWords`: the range of words starting with the prefix, and the prefix length
+/// in bytes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SortedNode {
+    start: usize,
+    end: usize,
+    depth: usize,
+}
+
+impl SortedWords {
+    pub fn new(words: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let mut words: Vec<String> = words.into_iter().map(Into::into).collect();
+        words.sort_unstable();
+        words.dedup();
+        SortedWords(words)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Dictionary for SortedWords {
+    type Node = SortedNode;
+
+    fn root(&self) -> SortedNode {
+        SortedNode {
+            start: 0,
+            end: self.0.len(),
+            depth: 0,
+        }
+    }
+
+    fn is_word(&self, node: &SortedNode) -> bool {
+        // the prefix itself sorts before every longer word starting with it
+        node.start < node.end && self.0[node.start].len() == node.depth
+    }
+
+    fn children(&self, node: &SortedNode) -> Vec<(char, SortedNode)> {
+        let mut children = Vec::new();
+        let mut start = node.start;
+        while start < node.end {
+            let Some(letter) = self.0[start][node.depth..].chars().next() else {
+                start += 1;
+                continue;
+            };
+            let end = start
+                + self.0[start..node.end]
+                    .partition_point(|word| word[node.depth..].starts_with(letter));
+            children.push((
+                letter,
+                SortedNode {
+                    start,
+                    end,
+                    depth: node.depth + letter.len_utf8(),
+                },
+            ));
+            start = end;
+        }
+        children
+    }
+
+    fn child(&self, node: &SortedNode, letter: char) -> Option<SortedNode> {
+        let mut buf = [0; 4];
+        let letter = letter.encode_utf8(&mut buf).as_bytes();
+        let depth = node.depth + letter.len();
+
+        // the bytes of each word after the prefix, up to the length of the letter
+        fn next(word: &str, from: usize, to: usize) -> &[u8] {
+            &word.as_bytes()[from..to.min(word.len())]
+        }
+        let words = &self.0[node.start..node.end];
+        let start = words.partition_point(|word| next(word, node.depth, depth) < letter);
+        let end = words.partition_point(|word| next(word, node.depth, depth) <= letter);
+
+        (start < end).then_some(SortedNode {
+            start: node.start + start,
+            end: node.start + end,
+            depth,
+        })
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.0.binary_search_by(|w| w.as_str().cmp(word)).is_ok()
+    }
+}
+
+/// The compact backend: a minimized automaton in which words share both their prefixes and
+/// their suffixes. The bundled word lists are built into these at compile time.
+#[derive(Clone)]
+pub struct FstWords(fst::Set<Cow<'static, [u8]>>);
+
+impl FstWords {
+    /// Builds the automaton from a list of words
+    pub fn new(words: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let SortedWords(words) = SortedWords::new(words);
+        let set = fst::Set::from_iter(words).expect("the words are sorted and deduplicated");
+        FstWords(set.map_data(Cow::Owned).unwrap())
+    }
+
+    /// Reads an automaton that was built before, like the ones written by the build script
+    pub fn from_bytes(bytes: &'static [u8]) -> Result<Self, fst::Error> {
+        fst::Set::new(Cow::Borrowed(bytes)).map(FstWords)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Follows the bytes of `letter` from the node at `addr`
+    fn follow_bytes(&self, addr: usize, letter: &[u8]) -> Option<usize> {
+        letter.iter().try_fold(addr, |addr, byte| {
+            let node = self.0.as_fst().node(addr);
+            node.find_input(*byte).map(|i| node.transition_addr(i))
+        })
+    }
+}
+
+impl Dictionary for FstWords {
+    /// The address of the node in the automaton
+    type Node = usize;
+
+    fn root(&self) -> usize {
+        self.0.as_fst().root().addr()
+    }
+
+    fn is_word(&self, node: &usize) -> bool {
+        self.0.as_fst().node(*node).is_final()
+    }
+
+    fn children(&self, node: &usize) -> Vec<(char, usize)> {
+        // the automaton works on bytes, so letters outside of ASCII span several transitions
+        let mut partial = vec![(Vec::new(), *node)];
+        let mut children = Vec::new();
+        while let Some((bytes, addr)) = partial.pop() {
+            for transition in self.0.as_fst().node(addr).transitions() {
+                let mut bytes = bytes.clone();
+                bytes.push(transition.inp);
+                match std::str::from_utf8(&bytes) {
+                    Ok(letter) => children.push((letter.chars().next().unwrap(), transition.addr)),
+                    Err(e) if e.error_len().is_none() => partial.push((bytes, transition.addr)),
+                    Err(_) => {}
+                }
+            }
+        }
+        children.sort_by_key(|(letter, _)| *letter);
+        children
+    }
+
+    fn child(&self, node: &usize, letter: char) -> Option<usize> {
+        let mut buf = [0; 4];
+        self.follow_bytes(*node, letter.encode_utf8(&mut buf).as_bytes())
+    }
+
+    fn follow(&self, node: &usize, letters: &str) -> Option<usize> {
+        self.follow_bytes(*node, letters.as_bytes())
+    }
+
+    fn walk(&self, prefix: &str) -> Option<usize> {
+        self.follow_bytes(self.root(), prefix.as_bytes())
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.0.contains(word)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: [&str; 7] = ["an", "and", "ant", "año", "añojo", "be", "bee"];
+
+    fn check_backend(dict: &impl Dictionary) {
+        for word in WORDS {
+            assert!(dict.contains(word), "{word}");
+        }
+        assert!(!dict.contains(""));
+        assert!(!dict.contains("a"));
+        assert!(!dict.contains("ano"));
+        assert!(!dict.contains("bees"));
+
+        let children = |prefix| {
+            let node = dict.walk(prefix).unwrap();
+            let letters: String = dict.children(&node).iter().map(|(c, _)| *c).collect();
+            letters
+        };
+        assert_eq!(children(""), "ab");
+        assert_eq!(children("a"), "nñ");
+        assert_eq!(children("an"), "dt");
+        assert_eq!(children("añ"), "o");
+        assert_eq!(children("bee"), "");
+
+        let an = dict.walk("an").unwrap();
+        assert!(dict.is_word(&an));
+        let and = dict.child(&an, 'd').unwrap();
+        assert!(dict.is_word(&and));
+        assert!(dict.child(&an, 'x').is_none());
+        assert!(!dict.is_word(&dict.walk("añoj").unwrap()));
+        assert!(dict.walk("c").is_none());
+
+        // walking the children of a node finds each word once
+        let mut found = Vec::new();
+        let mut stack = vec![(String::new(), dict.root())];
+        while let Some((prefix, node)) = stack.pop() {
+            if dict.is_word(&node) {
+                found.push(prefix.clone());
+            }
+            for (letter, child) in dict.children(&node) {
+                stack.push((format!("{prefix}{letter}"), child));
+            }
+        }
+        found.sort();
+        assert_eq!(found, SortedWords::new(WORDS).0);
+    }
+
+    #[test]
+    fn sorted_words() {
+        check_backend(&SortedWords::new(WORDS));
+    }
+
+    #[test]
+    fn fst_words() {
+        let dict = FstWords::new(WORDS);
+        assert_eq!(dict.len(), WORDS.len());
+        check_backend(&dict);
+    }
+}
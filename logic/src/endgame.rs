@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+
+use crate::{
+    game::Game,
+    language::Language,
+    movegen::{self, Move},
+    rules::{Rules, ScorelessLimit},
+    strategy::Choice,
+    Board, BoardTile, Coordinate, CrabbleError, Hand, HandTile, Tile,
+};
+
+/// The outcome of `solve`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solution {
+    /// The turns of both players from here to the end of the game, starting with the side to move
+    pub line: Vec<Choice>,
+    /// How far the side to move ends up ahead of the other player, after the leftover tiles are
+    /// settled
+    pub spread: isize,
+    /// Whether the whole game was searched. If not, the search stopped after `max_plies` turns
+    /// and guessed the rest as if both players passed from there.
+    pub exact: bool,
+}
+
+/// Searches the rest of a two player game once the bag is empty, and both racks are known.
+///
+/// The search deepens one turn at a time up to `max_plies`, with alpha-beta pruning and a table of
+/// the positions it has seen. Moves are tried highest score first, after the best move of the
+/// previous depth.
+pub fn solve(game: &Game, max_plies: usize) -> Result<Solution, CrabbleError> {
+    let player = game.current_player().ok_or(CrabbleError::GameOver)?;
+    if game.players.len() != 2 {
+        return Err(CrabbleError::EndgameNeedsTwoPlayers);
+    }
+    if game.tiles_in_bag() > 0 {
+        return Err(CrabbleError::BagNotEmpty);
+    }
+    game.legal_moves()?;
+
+    let opponent = 1 - player;
+    let mut board = game.board().clone();
+    for (coord, tile) in board.tiles_with_coordinates().collect::<Vec<_>>() {
+        if tile.is_some_and(|tile| tile.is_provisional) {
+            *board.get_tile_mut(coord).unwrap() = None;
+        }
+    }
+    board.provisionary_tiles_count = 0;
+
+    let mut solver = Solver {
+        language: game.language(),
+        rules: game.rules(),
+        scoreless_limit: match game.rules().scoreless_limit {
+            ScorelessLimit::Turns(turns) => turns,
+            ScorelessLimit::Rounds(rounds) => rounds * 2,
+        },
+        table: HashMap::new(),
+        horizon_reached: false,
+    };
+    let mut position = Position {
+        board,
+        placed: Vec::new(),
+        racks: [
+            game.players[player].hand.tiles().to_vec(),
+            game.players[opponent].hand.tiles().to_vec(),
+        ],
+        scoreless: game.scoreless_turns(),
+    };
+
+    let mut value = 0;
+    let mut exact = false;
+    for depth in 1..=max_plies.max(1) {
+        solver.horizon_reached = false;
+        value = solver.search(&mut position, depth, -isize::MAX, isize::MAX);
+        if !solver.horizon_reached {
+            exact = true;
+            break;
+        }
+    }
+
+    Ok(Solution {
+        line: solver.line(&mut position),
+        spread: game.players[player].score() - game.players[opponent].score() + value,
+        exact,
+    })
+}
+
+/// The part of the game that changes during the search. The side to move is always the first
+/// rack.
+struct Position {
+    board: Board,
+    /// The tiles put on the board during the search, sorted, so positions reached by playing the
+    /// same moves in another order are recognized
+    placed: Vec<(Coordinate, Tile)>,
+    racks: [Vec<HandTile>; 2],
+    scoreless: usize,
+}
+
+type Key = (Vec<(Coordinate, Tile)>, [Vec<HandTile>; 2], usize);
+
+impl Position {
+    fn key(&self) -> Key {
+        let rack = |rack: &[HandTile]| {
+            let mut tiles = rack.to_vec();
+            tiles.sort_unstable();
+            tiles
+        };
+        (
+            self.placed.clone(),
+            [rack(&self.racks[0]), rack(&self.racks[1])],
+            self.scoreless,
+        )
+    }
+
+    fn play(&mut self, play: &Move) {
+        for (coord, tile) in &play.tiles {
+            *self.board.get_tile_mut(*coord).unwrap() = Some(BoardTile {
+                tile: *tile,
+                is_provisional: false,
+            });
+            let idx = self.racks[0]
+                .iter()
+                .position(|t| *t == HandTile::from(*tile))
+                .unwrap();
+            self.racks[0].remove(idx);
+            self.placed.push((*coord, *tile));
+        }
+        self.placed.sort_by_key(|(coord, _)| (coord.x, coord.y));
+        self.racks.swap(0, 1);
+    }
+
+    fn undo_play(&mut self, play: &Move) {
+        self.racks.swap(0, 1);
+        for (coord, tile) in &play.tiles {
+            *self.board.get_tile_mut(*coord).unwrap() = None;
+            self.racks[0].push(HandTile::from(*tile));
+            self.placed.retain(|(c, _)| c != coord);
+        }
+    }
+}
+
+/// What is known about the value of a position
+#[derive(Clone, Copy, PartialEq)]
+enum Bound {
+    Exact,
+    /// The value is at least this much
+    Lower,
+    /// The value is at most this much
+    Upper,
+}
+
+struct Entry {
+    value: isize,
+    bound: Bound,
+    /// How many turns deep the position was searched
+    depth: usize,
+    /// Whether the search reached the end of the game everywhere below the position
+    complete: bool,
+    best: Option<Choice>,
+}
+
+struct Solver<'a> {
+    language: &'a Language,
+    rules: &'a Rules,
+    scoreless_limit: usize,
+    table: HashMap<Key, Entry>,
+    /// Set when a position had to be guessed because the search ran out of depth
+    horizon_reached: bool,
+}
+
+impl Solver<'_> {
+    fn leftover(&self, rack: &[HandTile]) -> isize {
+        rack.iter()
+            .map(|tile| self.language.values.value(*tile) as isize)
+            .sum()
+    }
+
+    /// The turns the side to move can take, the best one of an earlier search first
+    fn choices(&self, position: &Position, best: Option<&Choice>) -> Vec<Choice> {
+        let hand = Hand::from(position.racks[0].clone());
+        let mut choices: Vec<Choice> =
+            movegen::generate(&position.board, &hand, self.language, self.rules)
+                .into_iter()
+                .map(Choice::Play)
+                .collect();
+        choices.push(Choice::Pass);
+
+        if let Some(idx) = best.and_then(|best| choices.iter().position(|c| c == best)) {
+            let best = choices.remove(idx);
+            choices.insert(0, best);
+        }
+        choices
+    }
+
+    /// The value of `position` for the side to move: the points they will score from here on,
+    /// minus the points of the other player
+    fn search(
+        &mut self,
+        position: &mut Position,
+        depth: usize,
+        alpha: isize,
+        beta: isize,
+    ) -> isize {
+        if depth == 0 {
+            // guess that both players pass from here
+            self.horizon_reached = true;
+            return self.leftover(&position.racks[1]) - self.leftover(&position.racks[0]);
+        }
+
+        let key = position.key();
+        let mut best = None;
+        if let Some(entry) = self.table.get(&key) {
+            if entry.depth >= depth || entry.complete {
+                let usable = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.value >= beta,
+                    Bound::Upper => entry.value <= alpha,
+                };
+                if usable {
+                    self.horizon_reached |= !entry.complete;
+                    return entry.value;
+                }
+            }
+            best = entry.best.clone();
+        }
+
+        let horizon_before = self.horizon_reached;
+        self.horizon_reached = false;
+
+        let mut alpha_now = alpha;
+        let mut value = -isize::MAX;
+        let mut best_choice = None;
+        for choice in self.choices(position, best.as_ref()) {
+            let score = self.value_of(position, &choice, depth, alpha_now, beta);
+            if score > value {
+                value = score;
+                best_choice = Some(choice);
+            }
+            alpha_now = alpha_now.max(value);
+            if alpha_now >= beta {
+                break;
+            }
+        }
+
+        let complete = !self.horizon_reached;
+        self.horizon_reached |= horizon_before;
+        let bound = if value <= alpha {
+            Bound::Upper
+        } else if value >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.insert(
+            key,
+            Entry {
+                value,
+                bound,
+                depth,
+                complete,
+                best: best_choice,
+            },
+        );
+        value
+    }
+
+    /// The value for the side to move of taking the turn `choice`
+    fn value_of(
+        &mut self,
+        position: &mut Position,
+        choice: &Choice,
+        depth: usize,
+        alpha: isize,
+        beta: isize,
+    ) -> isize {
+        match choice {
+            Choice::Play(play) => {
+                if play.tiles.len() == position.racks[0].len() {
+                    // going out ends the game, and earns the tiles left on the other rack twice
+                    return play.score() + 2 * self.leftover(&position.racks[1]);
+                }
+
+                let scoreless = position.scoreless;
+                position.play(play);
+                position.scoreless = 0;
+                let value = play.score() - self.search(position, depth - 1, -beta, -alpha);
+                position.undo_play(play);
+                position.scoreless = scoreless;
+                value
+            }
+            Choice::Pass | Choice::Exchange(_) => {
+                if position.scoreless + 1 >= self.scoreless_limit {
+                    // the game ends, and both players lose their own tiles
+                    return self.leftover(&position.racks[1]) - self.leftover(&position.racks[0]);
+                }
+
+                position.racks.swap(0, 1);
+                position.scoreless += 1;
+                let value = -self.search(position, depth - 1, -beta, -alpha);
+                position.scoreless -= 1;
+                position.racks.swap(0, 1);
+                value
+            }
+        }
+    }
+
+    /// Follows the best moves in the table from `position`
+    fn line(&self, position: &mut Position) -> Vec<Choice> {
+        let mut line = Vec::new();
+        while let Some(choice) = self
+            .table
+            .get(&position.key())
+            .and_then(|entry| entry.best.clone())
+        {
+            line.push(choice.clone());
+            match &choice {
+                Choice::Play(play) => {
+                    let goes_out = play.tiles.len() == position.racks[0].len();
+                    position.play(play);
+                    position.scoreless = 0;
+                    if goes_out {
+                        break;
+                    }
+                }
+                Choice::Pass | Choice::Exchange(_) => {
+                    if position.scoreless + 1 >= self.scoreless_limit {
+                        break;
+                    }
+                    position.racks.swap(0, 1);
+                    position.scoreless += 1;
+                }
+            }
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::GameState, test_utils::*};
+
+    use super::*;
+
+    /// A game after "cat" was played through the center, with the bag emptied
+    fn endgame(racks: [&str; 2]) -> Game {
+        let mut game = make_game();
+        run_rigged("77hcat", &mut game).unwrap();
+
+        game.drain_bag(0);
+        game.players[0].hand = hand(racks[0]);
+        game.players[1].hand = hand(racks[1]);
+        game
+    }
+
+    #[test]
+    fn going_out_wins() {
+        // player 2 can go out with the "s", and catches the "q" left on the other rack
+        let game = endgame(["q", "s"]);
+        assert_eq!(game.current_player(), Some(1));
+
+        let solution = solve(&game, 8).unwrap();
+        assert!(solution.exact);
+        let Choice::Play(play) = &solution.line[0] else {
+            panic!("{solution:?}");
+        };
+        let best = game.legal_moves().unwrap()[0].clone();
+        assert_eq!(*play, best);
+        assert_eq!(solution.line.len(), 1);
+        let behind = game.players[1].score() - game.players[0].score();
+        assert_eq!(solution.spread, behind + play.score() + 2 * 10);
+    }
+
+    #[test]
+    fn stuck_players_pass() {
+        // neither rack fits anywhere, so the game ends in passes
+        let game = endgame(["q", "qv"]);
+        let solution = solve(&game, 8).unwrap();
+
+        assert!(solution.exact);
+        assert!(solution.line.iter().all(|choice| *choice == Choice::Pass));
+        assert_eq!(solution.line.len(), 6);
+        // player 2 is left with "qv" and player 1 with "q"
+        let behind = game.players[1].score() - game.players[0].score();
+        assert_eq!(solution.spread, behind - 14 + 10);
+    }
+
+    #[test]
+    fn line_plays_out_to_the_spread() {
+        let mut game = endgame(["dog", "ear"]);
+        assert!(!solve(&game, 1).unwrap().exact);
+
+        let solution = solve(&game, 12).unwrap();
+        assert!(solution.exact);
+        for choice in &solution.line {
+            game.play_choice(choice).unwrap();
+        }
+        assert_eq!(game.state, GameState::Done);
+        assert_eq!(
+            game.players[1].score() - game.players[0].score(),
+            solution.spread
+        );
+    }
+
+    #[test]
+    fn only_endgames_are_solved() {
+        let mut game = make_game();
+        assert_eq!(solve(&game, 2).unwrap_err(), CrabbleError::BagNotEmpty);
+
+        game.state = GameState::Done;
+        assert_eq!(solve(&game, 2).unwrap_err(), CrabbleError::GameOver);
+    }
+}
@@ -1,15 +1,30 @@
 use std::fmt::Display;
 
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
 use crate::{
-    bag::Bag, language::Language, Board, BoardLayout, BoardTile, Coordinate, CrabbleError,
-    Direction, Hand, HandTile, Square, Tile,
+    asn::ASNLine,
+    bag::Bag,
+    challenge, formed_words,
+    history::{TurnAction, TurnRecord},
+    language::Language,
+    movegen::{self, Hint, Move},
+    rules::{ChallengeOutcome, ChallengeRule, HintPolicy, Rules, ScorelessLimit, Validation},
+    score::{LetterScore, ScoreBreakdown, Standing, WordScore},
+    strategy::{Bot, Choice},
+    word_string, Board, BoardLayout, BoardTile, Coordinate, CrabbleError, Direction, Hand,
+    HandTile, Square, Tile,
 };
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Player {
     name: String,
     score: isize,
     pub hand: Hand,
+    /// The computer player taking this seat's turns, if it isn't played by a person
+    pub bot: Option<Bot>,
 }
 
 impl Player {
@@ -18,13 +33,31 @@ impl Player {
             name,
             score: 0,
             hand: Hand::empty(),
+            bot: None,
         }
     }
+
+    pub fn computer(name: String, bot: Bot) -> Self {
+        Player {
+            bot: Some(bot),
+            ..Player::new(name)
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn score(&self) -> isize {
+        self.score
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameState {
-    /// Turn of a player referenced by index
+    /// Turn of a player referenced by index. The flag is set when the previous player went out,
+    /// in which case this turn can only be used to challenge that play or to accept it by passing.
     Turn(usize, bool),
     Done,
 }
@@ -39,8 +72,8 @@ impl Display for GameState {
 }
 
 impl Player {
-    pub fn draw_from_bag(&mut self, bag: &mut Bag) -> Result<(), CrabbleError> {
-        while self.hand.letters.len() < 7 {
+    pub fn draw_from_bag(&mut self, bag: &mut Bag, rack_size: usize) -> Result<(), CrabbleError> {
+        while self.hand.letters.len() < rack_size {
             let drawn_tile = bag.take();
             match drawn_tile {
                 Some(x) => self.hand.letters.push(x),
@@ -51,19 +84,74 @@ impl Player {
     }
 }
 
-#[derive(Debug)]
+/// The last committed play, kept around so the next player can challenge it
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ChallengeablePlay {
+    player: usize,
+    placed: Vec<(Coordinate, HandTile)>,
+    dir: Direction,
+    score: isize,
+    drawn: Vec<HandTile>,
+    state_before: GameState,
+    scoreless_before: usize,
+}
+
+/// Provisional tiles that passed the checks of `end_turn`
+struct CheckedPlay {
+    player: usize,
+    /// Where the main word starts
+    first: Coordinate,
+    dir: Direction,
+    played: Vec<(Coordinate, HandTile)>,
+}
+
+/// Everything about a game that changes from turn to turn, so turns can be undone and redone
+#[derive(Clone, Debug)]
+struct Snapshot {
+    board: Board,
+    bag: Bag,
+    rng: ChaCha8Rng,
+    players: Vec<Player>,
+    state: GameState,
+    challengeable: Option<ChallengeablePlay>,
+    scoreless_turns: usize,
+    standings: Vec<Standing>,
+    history: Vec<TurnRecord>,
+    hints_this_turn: usize,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Game {
     board: Board,
     bag: Bag,
     pub players: Vec<Player>,
     pub state: GameState,
     language: Language,
+    rules: Rules,
+    seed: u64,
+    rng: ChaCha8Rng,
+    challengeable: Option<ChallengeablePlay>,
+    scoreless_turns: usize,
+    standings: Vec<Standing>,
+    history: Vec<TurnRecord>,
+    /// How many hints the current player asked for since the last committed turn
+    hints_this_turn: usize,
+    // undo history is not part of a saved game
+    #[cfg_attr(feature = "serde", serde(skip))]
+    undo_stack: Vec<Snapshot>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    redo_stack: Vec<Snapshot>,
 }
 
 impl Display for Game {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "State: {}, Players: ", &self.state).unwrap();
-        for Player { hand, score, name } in &self.players {
+        for Player {
+            hand, score, name, ..
+        } in &self.players
+        {
             writeln!(f, "Player {name}, (score: {score}), hand: {hand}")?;
         }
 
@@ -74,17 +162,39 @@ impl Display for Game {
 }
 
 impl Game {
-    pub fn new(mut players: Vec<Player>, board_layout: BoardLayout, language: Language) -> Self {
+    pub fn new(players: Vec<Player>, board_layout: BoardLayout, language: Language) -> Self {
+        Game::with_rules(players, board_layout, language, Rules::default())
+    }
+
+    pub fn with_rules(
+        players: Vec<Player>,
+        board_layout: BoardLayout,
+        language: Language,
+        rules: Rules,
+    ) -> Self {
+        let seed = rand::thread_rng().gen();
+        Game::seeded(players, board_layout, language, rules, seed)
+    }
+
+    /// Creates a game whose bag is shuffled and refilled from `seed`, so two games with the same
+    /// seed deal the same tiles for the same moves.
+    pub fn seeded(
+        mut players: Vec<Player>,
+        board_layout: BoardLayout,
+        language: Language,
+        rules: Rules,
+        seed: u64,
+    ) -> Self {
         assert!(!players.is_empty());
-        assert!(players.len() >= 1);
         assert!(players.len() <= 4);
 
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
         let mut bag = Bag::full(&language.distribution);
-        bag.shuffle();
+        bag.shuffle(&mut rng);
 
         for player in &mut players {
-            player.draw_from_bag(&mut bag).unwrap();
-            assert_eq!(player.hand.letters.len(), 7);
+            player.draw_from_bag(&mut bag, language.rack_size).unwrap();
+            assert_eq!(player.hand.letters.len(), language.rack_size);
         }
 
         Self {
@@ -93,6 +203,16 @@ impl Game {
             state: GameState::Turn(0, false),
             language,
             players,
+            rules,
+            seed,
+            rng,
+            challengeable: None,
+            scoreless_turns: 0,
+            standings: Vec::new(),
+            history: Vec::new(),
+            hints_this_turn: 0,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
         }
     }
 
@@ -100,6 +220,139 @@ impl Game {
         &self.board
     }
 
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// The language of the game, including the lexicons words are checked against
+    pub fn language(&self) -> &Language {
+        &self.language
+    }
+
+    /// Every play the current player could make with their rack, highest score first
+    pub fn legal_moves(&self) -> Result<Vec<Move>, CrabbleError> {
+        let GameState::Turn(player_id, is_last_round) = self.state else {
+            return Err(CrabbleError::GameOver);
+        };
+        if is_last_round {
+            return Err(CrabbleError::LastPlayWentOut);
+        }
+
+        Ok(movegen::generate(
+            &self.board,
+            &self.players[player_id].hand,
+            &self.language,
+            &self.rules,
+        ))
+    }
+
+    /// The `n` best scoring moves for the current player. Each call counts as a hint in the
+    /// history of the turn, and in the `HintPolicy` of the rules.
+    pub fn hints(&mut self, n: usize) -> Result<Vec<Hint>, CrabbleError> {
+        let player_id = self.current_player().ok_or(CrabbleError::GameOver)?;
+        match self.rules.hints {
+            HintPolicy::Off => return Err(CrabbleError::HintsDisabled),
+            HintPolicy::Limited(limit) if self.hints_used(player_id) >= limit => {
+                return Err(CrabbleError::NoHintsLeft)
+            }
+            HintPolicy::Limited(_) | HintPolicy::Unlimited => (),
+        }
+
+        let mut moves = self.legal_moves()?;
+        moves.truncate(n);
+        self.hints_this_turn += 1;
+
+        let rack = self.players[player_id].hand.tiles();
+        Ok(moves
+            .into_iter()
+            .map(|play| Hint {
+                asn: ASNLine::from(&play).to_string(),
+                score: play.score(),
+                words: play.words.clone(),
+                leave: play.leave(rack),
+                play,
+            })
+            .collect())
+    }
+
+    /// How many times `player` asked for hints so far this game
+    pub fn hints_used(&self, player: usize) -> usize {
+        let pending = match self.current_player() {
+            Some(current) if current == player => self.hints_this_turn,
+            _ => 0,
+        };
+        self.history
+            .iter()
+            .filter(|record| record.player == player)
+            .map(|record| record.hints)
+            .sum::<usize>()
+            + pending
+    }
+
+    /// Plays `play` as the current player's turn, replacing any tiles they placed so far. If the
+    /// play is refused, the board is left without provisional tiles.
+    pub fn play_move(&mut self, play: &Move) -> Result<ScoreBreakdown, CrabbleError> {
+        self.recall_all();
+        let result = play
+            .tiles
+            .iter()
+            .try_for_each(|(coord, tile)| self.place_tile(*tile, *coord))
+            .and_then(|()| self.end_turn());
+        if result.is_err() {
+            self.recall_all();
+        }
+        result
+    }
+
+    /// Lets the computer player in the current seat take its turn
+    pub fn play_bot_turn(&mut self) -> Result<(), CrabbleError> {
+        let player_id = self.current_player().ok_or(CrabbleError::GameOver)?;
+        let bot = self.players[player_id].bot.ok_or(CrabbleError::NotABot)?;
+
+        let choice = bot.strategy(&self.language).choose(self);
+        self.play_choice(&choice)
+    }
+
+    /// Takes the turn a `Strategy` chose for the current player
+    pub fn play_choice(&mut self, choice: &Choice) -> Result<(), CrabbleError> {
+        match choice {
+            Choice::Play(play) => self.play_move(play).map(|_| ()),
+            Choice::Exchange(tiles) => self.exchange(tiles),
+            Choice::Pass => {
+                self.recall_all();
+                self.pass()
+            }
+        }
+    }
+
+    /// The number of tiles left in the bag
+    pub fn tiles_in_bag(&self) -> usize {
+        self.bag.len()
+    }
+
+    /// Whether the current player could exchange `count` tiles this turn, going by the state of
+    /// the game and the tiles left in the bag
+    pub fn can_exchange(&self, count: usize) -> bool {
+        matches!(self.state, GameState::Turn(_, false))
+            && count > 0
+            && self.bag.len() >= self.rules.exchange_min_bag.max(count)
+    }
+
+    /// The seed the game was created with, which is enough to replay it exactly
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Every turn that has been committed so far, oldest first
+    pub fn history(&self) -> &[TurnRecord] {
+        &self.history
+    }
+
+    /// The number of scoreless turns that have been played in a row
+    pub fn scoreless_turns(&self) -> usize {
+        self.scoreless_turns
+    }
+
     pub fn get_tile(&self, coord: Coordinate) -> Option<BoardTile> {
         self.board.get_tile(coord)
     }
@@ -110,15 +363,49 @@ impl Game {
         if let GameState::Turn(t, _is_last_round) = self.state {
             let current_player_hand = &self.players.get(t).unwrap().hand;
             for letter in &current_player_hand.letters {
-                let score = self.language.values.get(*letter);
-                let tile = format!("({}, {}) ", letter.to_string(), score);
+                let score = self.language.values.value(*letter);
+                let tile = format!("({letter}, {score}) ");
                 res.push_str(&tile);
             }
         }
         res
     }
 
+    /// Index of the player whose turn it currently is, if the game is still going
+    pub fn current_player(&self) -> Option<usize> {
+        match self.state {
+            GameState::Turn(n, _is_last_round) => Some(n),
+            GameState::Done => None,
+        }
+    }
+
+    /// The tiles that have been provisionally placed on the board this turn
+    fn provisional_tiles(&self) -> impl Iterator<Item = (Coordinate, BoardTile)> + '_ {
+        provisional_tiles(&self.board)
+    }
+
     pub fn place_tile(&mut self, tile: Tile, coord: Coordinate) -> Result<(), CrabbleError> {
+        let GameState::Turn(player_id, is_last_round) = self.state else {
+            return Err(CrabbleError::GameOver);
+        };
+        if is_last_round {
+            return Err(CrabbleError::LastPlayWentOut);
+        }
+
+        // the letter may be written in any case or encoding, but has to be one of the language
+        let tile = Tile::new(self.language.letter(tile.tile.as_str())?, tile.is_joker);
+
+        // the tile has to come from the player's hand, on top of the ones
+        // that are already on the board this turn
+        let hand_tile = HandTile::from(tile);
+        let already_placed = self
+            .provisional_tiles()
+            .filter(|(_, t)| HandTile::from(t.tile) == hand_tile)
+            .count();
+        if self.players[player_id].hand.count(hand_tile) <= already_placed {
+            return Err(CrabbleError::TileNotInHand);
+        }
+
         // is_provisionary is true
         // we place the tiles on
         let board_tile = self
@@ -139,199 +426,866 @@ impl Game {
         }
     }
 
-    pub fn end_turn(&mut self) -> Result<(), CrabbleError> {
-        let mut tile_iter = self
+    /// Takes the provisional tile at `coord` back off the board.
+    ///
+    /// Placed tiles only leave the rack once the turn is committed, so a recalled tile is free to
+    /// be placed again straight away.
+    pub fn recall_tile(&mut self, coord: Coordinate) -> Result<Tile, CrabbleError> {
+        let board_tile = self
             .board
-            .tiles_with_coordinates()
-            .filter_map(|(coord, tile)| match tile {
-                Some(tile) if tile.is_provisional == true => Some((coord, tile)),
-                _ => None,
-            });
-
-        // check that we have played at least a tile
-        let Some((first_coord, _)) = tile_iter.next() else {
-            return Err(CrabbleError::PlayedWordEmpty);
+            .get_tile_mut(coord)
+            .ok_or(CrabbleError::TileOutOufBounds)?;
+
+        match board_tile {
+            Some(tile) if tile.is_provisional => {
+                let tile = tile.tile;
+                *board_tile = None;
+                self.board.provisionary_tiles_count -= 1;
+                Ok(tile)
+            }
+            _ => Err(CrabbleError::NoProvisionalTile),
+        }
+    }
+
+    /// Takes every provisional tile back off the board, returning them
+    pub fn recall_all(&mut self) -> Vec<Tile> {
+        let coords: Vec<Coordinate> = self.provisional_tiles().map(|(coord, _)| coord).collect();
+
+        coords
+            .into_iter()
+            .map(|coord| self.recall_tile(coord).unwrap())
+            .collect()
+    }
+
+    /// Moves the provisional tile at `from` to the empty square `to`
+    pub fn move_provisional(
+        &mut self,
+        from: Coordinate,
+        to: Coordinate,
+    ) -> Result<(), CrabbleError> {
+        match self.board.get_tile(from) {
+            Some(tile) if tile.is_provisional => (),
+            _ => return Err(CrabbleError::NoProvisionalTile),
+        }
+        match self.board.get_tile_mut(to) {
+            None => return Err(CrabbleError::TileOutOufBounds),
+            Some(Some(_)) => return Err(CrabbleError::TileOccupied),
+            Some(None) => (),
+        }
+
+        let tile = self.board.get_tile_mut(from).unwrap().take();
+        *self.board.get_tile_mut(to).unwrap() = tile;
+
+        Ok(())
+    }
+
+    /// Checks the provisional tiles like `end_turn` does and returns what they would score,
+    /// without committing them
+    pub fn preview_turn(&self) -> Result<ScoreBreakdown, CrabbleError> {
+        let play = self.check_play()?;
+
+        Ok(score_word(
+            &self.board,
+            &self.language,
+            &self.rules,
+            self.board.find_range(play.first, play.dir),
+            play.dir,
+        ))
+    }
+
+    /// Checks that the provisional tiles make a play the current player may commit
+    fn check_play(&self) -> Result<CheckedPlay, CrabbleError> {
+        let GameState::Turn(player_id, is_last_round) = self.state else {
+            return Err(CrabbleError::GameOver);
         };
+        if is_last_round {
+            return Err(CrabbleError::LastPlayWentOut);
+        }
+
+        let (first_coord, dir) = placed_word(&self.board)?;
 
-        // check that we have played tiles in a (straight) line
-        let mut axes = (Some(first_coord.x), Some(first_coord.y));
-        for (c, _) in tile_iter {
-            if Some(c.x) != axes.0 {
-                axes.0 = None;
+        // check that the current player actually holds every tile they played
+        let played: Vec<(Coordinate, HandTile)> = self
+            .provisional_tiles()
+            .map(|(coord, tile)| (coord, HandTile::from(tile.tile)))
+            .collect();
+        let hand = &self.players[player_id].hand;
+        for (_, tile) in &played {
+            let needed = played.iter().filter(|(_, t)| t == tile).count();
+            if hand.count(*tile) < needed {
+                return Err(CrabbleError::TileNotInHand);
             }
-            if Some(c.y) != axes.1 {
-                axes.1 = None;
+        }
+
+        if self.rules.validation == Validation::Auto {
+            let placed: Vec<Coordinate> = played.iter().map(|(coord, _)| *coord).collect();
+            let invalid = challenge(&self.board, &placed, dir, &self.language);
+            if !invalid.is_empty() {
+                return Err(CrabbleError::InvalidWords(invalid));
             }
         }
 
-        // select direction for gap check
-        let dir = match axes {
-            (Some(_), Some(_)) => {
-                let h = self
-                    .board
-                    .find_range(first_coord, Direction::Horizontal)
-                    .collect::<Vec<_>>();
-                let v = self
-                    .board
-                    .find_range(first_coord, Direction::Vertical)
-                    .collect::<Vec<_>>();
-                if h.len() > v.len() {
-                    Direction::Horizontal
-                } else {
-                    Direction::Vertical
-                }
+        Ok(CheckedPlay {
+            player: player_id,
+            first: first_coord,
+            dir,
+            played,
+        })
+    }
+
+    pub fn end_turn(&mut self) -> Result<ScoreBreakdown, CrabbleError> {
+        let CheckedPlay {
+            player: player_id,
+            first: first_coord,
+            dir,
+            played,
+        } = self.check_play()?;
+
+        let breakdown = score_word(
+            &self.board,
+            &self.language,
+            &self.rules,
+            self.board.find_range(first_coord, dir),
+            dir,
+        );
+        let score = breakdown.total();
+
+        let tiles: Vec<(Coordinate, Tile)> = self
+            .provisional_tiles()
+            .map(|(coord, tile)| (coord, tile.tile))
+            .collect();
+        let placed: Vec<Coordinate> = tiles.iter().map(|(coord, _)| *coord).collect();
+        let words = formed_words(&self.board, &placed, dir)
+            .iter()
+            .map(|word| word_string(&self.board, word))
+            .collect();
+        let rack_before = self.players[player_id].hand.letters.clone();
+
+        // the play is valid, so commit it to the board and take the tiles out of the rack
+        self.checkpoint();
+        for (coord, _) in &played {
+            self.board
+                .get_tile_mut(*coord)
+                .unwrap()
+                .as_mut()
+                .unwrap()
+                .is_provisional = false;
+        }
+        self.board.provisionary_tiles_count = 0;
+
+        let current_player = self.players.get_mut(player_id).unwrap();
+        for (_, tile) in &played {
+            current_player.hand.remove(*tile)?;
+        }
+        current_player.score += score;
+
+        let hand_size = current_player.hand.letters.len();
+        let _ = current_player.draw_from_bag(&mut self.bag, self.language.rack_size);
+        let drawn = current_player.hand.letters[hand_size..].to_vec();
+
+        self.history.push(TurnRecord {
+            player: player_id,
+            action: TurnAction::Play {
+                tiles,
+                words,
+                breakdown: breakdown.clone(),
+                withdrawn: false,
+            },
+            score,
+            rack_before,
+            rack_after: current_player.hand.letters.clone(),
+            hints: std::mem::take(&mut self.hints_this_turn),
+        });
+
+        self.challengeable = match self.rules.validation {
+            Validation::Challenge(_) => Some(ChallengeablePlay {
+                player: player_id,
+                placed: played,
+                dir,
+                score,
+                drawn,
+                state_before: self.state,
+                scoreless_before: self.scoreless_turns,
+            }),
+            Validation::Off | Validation::Auto => None,
+        };
+        self.scoreless_turns = 0;
+
+        let went_out = current_player.hand.is_empty();
+        let next_player = (player_id + 1) % self.players.len();
+        self.state = GameState::Turn(next_player, false);
+        if self.bag.is_empty() && went_out {
+            match self.rules.validation {
+                // the next player still gets the chance to challenge the play that went out
+                Validation::Challenge(_) => self.state = GameState::Turn(next_player, true),
+                Validation::Off | Validation::Auto => self.finish_game(),
             }
-            (Some(_), _) => Direction::Vertical,
-            (_, Some(_)) => Direction::Horizontal,
-            (None, None) => return Err(CrabbleError::InvalidDirection),
+        }
+
+        Ok(breakdown)
+    }
+
+    /// Exchanges `tiles` from the current player's hand for the same number of tiles from the
+    /// bag, using up their turn.
+    pub fn exchange(&mut self, tiles: &[HandTile]) -> Result<(), CrabbleError> {
+        let GameState::Turn(player_id, is_last_round) = self.state else {
+            return Err(CrabbleError::GameOver);
         };
+        if is_last_round {
+            return Err(CrabbleError::LastPlayWentOut);
+        }
+        if tiles.is_empty() {
+            return Err(CrabbleError::ExchangeEmpty);
+        }
+        if self.board.provisionary_tiles_count > 0 {
+            return Err(CrabbleError::TilesAlreadyPlaced);
+        }
+        if self.bag.len() < self.rules.exchange_min_bag.max(tiles.len()) {
+            return Err(CrabbleError::BagTooSmallToExchange);
+        }
 
-        let coords_vec: Vec<Coordinate> = self.board.find_range(first_coord, dir).collect();
+        let hand = &self.players[player_id].hand;
+        for tile in tiles {
+            let needed = tiles.iter().filter(|t| *t == tile).count();
+            if hand.count(*tile) < needed {
+                return Err(CrabbleError::TileNotInHand);
+            }
+        }
 
-        // check that there are no provisional tiles in the board
-        // that aren't in this range
-        let all_tiles = self
-            .board
-            .tiles_with_coordinates()
-            .filter(|(_, t)| t.is_some_and(|t| t.is_provisional));
-        for (coord, tile) in all_tiles {
-            if !coords_vec.contains(&coord) {
-                println!("{coord:?}");
-                println!("{tile:?}");
-
-                return Err(CrabbleError::ScatteredProvisionalTile);
+        self.checkpoint();
+
+        // draw the new tiles before the old ones go back, so they can't be drawn again
+        let player = &mut self.players[player_id];
+        let rack_before = player.hand.letters.clone();
+        for tile in tiles {
+            player.hand.remove(*tile)?;
+        }
+        let _ = player.draw_from_bag(&mut self.bag, self.language.rack_size);
+        for tile in tiles {
+            self.bag.put(*tile, &mut self.rng);
+        }
+
+        self.history.push(TurnRecord {
+            player: player_id,
+            action: TurnAction::Exchange {
+                tiles: tiles.to_vec(),
+            },
+            score: 0,
+            rack_before,
+            rack_after: player.hand.letters.clone(),
+            hints: std::mem::take(&mut self.hints_this_turn),
+        });
+
+        self.challengeable = None;
+        let next_player = (player_id + 1) % self.players.len();
+        self.state = GameState::Turn(next_player, is_last_round);
+        self.record_scoreless_turn();
+
+        Ok(())
+    }
+
+    /// Passes the current player's turn without playing anything.
+    ///
+    /// If the previous player went out, passing accepts their play and ends the game.
+    pub fn pass(&mut self) -> Result<(), CrabbleError> {
+        let GameState::Turn(player_id, is_last_round) = self.state else {
+            return Err(CrabbleError::GameOver);
+        };
+        if self.board.provisionary_tiles_count > 0 {
+            return Err(CrabbleError::TilesAlreadyPlaced);
+        }
+
+        self.checkpoint();
+
+        let rack = self.players[player_id].hand.letters.clone();
+        self.history.push(TurnRecord {
+            player: player_id,
+            action: TurnAction::Pass,
+            score: 0,
+            rack_before: rack.clone(),
+            rack_after: rack,
+            hints: std::mem::take(&mut self.hints_this_turn),
+        });
+
+        if is_last_round {
+            self.finish_game();
+            return Ok(());
+        }
+
+        self.challengeable = None;
+        let next_player = (player_id + 1) % self.players.len();
+        self.state = GameState::Turn(next_player, is_last_round);
+        self.record_scoreless_turn();
+
+        Ok(())
+    }
+
+    /// Captures the position at the start of the current turn, so without provisional tiles
+    fn snapshot(&self) -> Snapshot {
+        let mut board = self.board.clone();
+        for (coord, _) in self.provisional_tiles() {
+            *board.get_tile_mut(coord).unwrap() = None;
+        }
+        board.provisionary_tiles_count = 0;
+
+        Snapshot {
+            board,
+            bag: self.bag.clone(),
+            rng: self.rng.clone(),
+            players: self.players.clone(),
+            state: self.state,
+            challengeable: self.challengeable.clone(),
+            scoreless_turns: self.scoreless_turns,
+            standings: self.standings.clone(),
+            history: self.history.clone(),
+            hints_this_turn: self.hints_this_turn,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.board = snapshot.board;
+        self.bag = snapshot.bag;
+        self.rng = snapshot.rng;
+        self.players = snapshot.players;
+        self.state = snapshot.state;
+        self.challengeable = snapshot.challengeable;
+        self.scoreless_turns = snapshot.scoreless_turns;
+        self.standings = snapshot.standings;
+        self.history = snapshot.history;
+        self.hints_this_turn = snapshot.hints_this_turn;
+    }
+
+    /// Remembers the position before a turn is committed, so it can be undone later.
+    /// Committing a new turn throws away whatever could have been redone.
+    fn checkpoint(&mut self) {
+        self.undo_stack.push(self.snapshot());
+        self.redo_stack.clear();
+    }
+
+    /// Steps back to the position before the last committed turn, including the racks and the
+    /// order of the tiles in the bag. Any provisional tiles are taken off the board first.
+    pub fn undo(&mut self) -> Result<(), CrabbleError> {
+        let snapshot = self.undo_stack.pop().ok_or(CrabbleError::NothingToUndo)?;
+        self.recall_all();
+        self.redo_stack.push(self.snapshot());
+        self.restore(snapshot);
+        Ok(())
+    }
+
+    /// Steps forward again to the position after a turn that was undone
+    pub fn redo(&mut self) -> Result<(), CrabbleError> {
+        let snapshot = self.redo_stack.pop().ok_or(CrabbleError::NothingToRedo)?;
+        self.recall_all();
+        self.undo_stack.push(self.snapshot());
+        self.restore(snapshot);
+        Ok(())
+    }
+
+    /// A copy of the game to try moves out on, without the undo history that a clone would copy
+    /// along with it. Undoing in the copy goes no further back than the copy itself.
+    pub fn without_undo_history(&self) -> Game {
+        Game {
+            board: self.board.clone(),
+            bag: self.bag.clone(),
+            players: self.players.clone(),
+            state: self.state,
+            language: self.language.clone(),
+            rules: self.rules.clone(),
+            seed: self.seed,
+            rng: self.rng.clone(),
+            challengeable: self.challengeable.clone(),
+            scoreless_turns: self.scoreless_turns,
+            standings: self.standings.clone(),
+            history: self.history.clone(),
+            hints_this_turn: self.hints_this_turn,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    /// A copy of the game as `player` could imagine it: the tiles they can't see, in the bag and
+    /// on the other racks, are dealt again at random. The undo history is left out.
+    pub(crate) fn imagine(&self, player: usize, rng: &mut impl Rng) -> Game {
+        let mut game = Game {
+            rng: ChaCha8Rng::seed_from_u64(rng.gen()),
+            ..self.without_undo_history()
+        };
+
+        let mut unseen = Vec::new();
+        while let Some(tile) = game.bag.take() {
+            unseen.push(tile);
+        }
+        for (i, other) in game.players.iter().enumerate() {
+            if i != player {
+                unseen.extend_from_slice(&other.hand.letters);
             }
         }
+        unseen.shuffle(rng);
 
-        // check that there are unprovisional tiles in the range
-        // if not, then check that the range is adjacent to at least one
-        // non-provisional tile
-        if self
-            .board
-            .find_range(first_coord, dir)
-            .all(|coord| self.get_tile(coord).unwrap().is_provisional)
-        {
-            let other_dir = dir.flip();
-            let mut is_adjacent = false;
-
-            // for each adjacent tile in the other direction check that it's a valid word
-            for position in coords_vec {
-                let range = self
-                    .board
-                    .find_range(position, other_dir)
-                    .map(|coord| self.get_tile(coord).unwrap());
-                let range_vec: Vec<BoardTile> = range.collect();
-
-                if range_vec.len() > 1 {
-                    is_adjacent = true;
-                }
+        for (i, other) in game.players.iter_mut().enumerate() {
+            if i != player {
+                let count = other.hand.letters.len();
+                other.hand.letters = unseen.split_off(unseen.len() - count);
+            }
+        }
+        for tile in unseen {
+            game.bag.put(tile, rng);
+        }
+
+        game
+    }
+
+    /// Checks what a loaded game can't be trusted with: that the player to move exists, and that
+    /// the racks, the bag and the board hold exactly the tiles of the language
+    pub(crate) fn check_consistency(&self) -> Result<(), String> {
+        if let GameState::Turn(n, _) = self.state {
+            if n >= self.players.len() {
+                return Err(format!(
+                    "it is the turn of player {n}, but there are only {} players",
+                    self.players.len()
+                ));
             }
+        }
+
+        let mut tiles: Vec<HandTile> = self
+            .players
+            .iter()
+            .flat_map(|player| player.hand.letters.iter().copied())
+            .collect();
+        let mut bag = self.bag.clone();
+        while let Some(tile) = bag.take() {
+            tiles.push(tile);
+        }
+        tiles.extend(
+            self.board
+                .tiles_with_coordinates()
+                .filter_map(|(_, tile)| tile)
+                .map(|tile| HandTile::from(tile.tile)),
+        );
+        tiles.sort();
+
+        let mut expected: Vec<HandTile> = self
+            .language
+            .distribution
+            .iter()
+            .flat_map(|(tile, amount)| std::iter::repeat_n(tile, amount))
+            .collect();
+        expected.sort();
+        if tiles != expected {
+            return Err(format!(
+                "the racks, the bag and the board hold {} tiles, which are not the {} tiles of {}",
+                tiles.len(),
+                expected.len(),
+                self.language.name
+            ));
+        }
 
-            if !is_adjacent {
-                if !self.board.find_range(first_coord, dir).any(|coordinate| {
-                    self.board
-                        .get_square(coordinate)
-                        .unwrap()
-                        .eq(&Square::CenterSquare)
-                }) {
-                    return Err(CrabbleError::WordNotAdjacent);
+        Ok(())
+    }
+
+    /// Gives `player` exactly the tiles of `rack`, taken from the bag or, when the bag has none
+    /// left, from the other racks, so the game still holds the tiles of its language
+    #[cfg(test)]
+    pub(crate) fn rig_rack(&mut self, player: usize, rack: &[HandTile]) {
+        let mut pool = std::mem::take(&mut self.players[player].hand.letters);
+        while let Some(tile) = self.bag.take() {
+            pool.push(tile);
+        }
+
+        let mut missing = Vec::new();
+        for tile in rack {
+            match pool.iter().position(|t| t == tile) {
+                Some(i) => {
+                    pool.swap_remove(i);
                 }
+                None => missing.push(*tile),
             }
         }
+        for tile in missing {
+            let other = self
+                .players
+                .iter_mut()
+                .find(|other| other.hand.letters.contains(&tile))
+                .unwrap_or_else(|| panic!("no {tile} is left to rig the rack with"));
+            let i = other.hand.letters.iter().position(|t| *t == tile).unwrap();
+            other.hand.letters[i] = pool.pop().expect("no tile is left to swap with");
+        }
 
-        let score = self.score_word(self.board.find_range(first_coord, dir), dir);
+        self.players[player].hand.letters = rack.to_vec();
+        for tile in pool {
+            self.bag.put(tile, &mut self.rng);
+        }
+    }
 
-        match self.state {
-            GameState::Turn(n, is_last_round) => {
-                let player_id = n % self.players.len();
-                let current_player = self.players.get_mut(player_id).unwrap();
-                current_player.score += score;
-
-                let _ = current_player.draw_from_bag(&mut self.bag);
-
-                if self.bag.is_empty() && current_player.hand.is_empty() {
-                    // starting final round
-                    self.state = GameState::Turn(n + 1, true);
-                } else {
-                    self.state = GameState::Turn(n + 1, is_last_round);
+    /// Takes tiles out of the bag until `left` are left, to get to the end of a game quickly
+    #[cfg(test)]
+    pub(crate) fn drain_bag(&mut self, left: usize) {
+        while self.bag.len() > left {
+            self.bag.take();
+        }
+    }
+
+    /// Counts a turn in which nobody scored, ending the game once the
+    /// `ScorelessLimit` of the rules is reached.
+    fn record_scoreless_turn(&mut self) {
+        self.scoreless_turns += 1;
+
+        let limit = match self.rules.scoreless_limit {
+            ScorelessLimit::Turns(turns) => turns,
+            ScorelessLimit::Rounds(rounds) => rounds * self.players.len(),
+        };
+        if self.scoreless_turns >= limit {
+            self.finish_game();
+        }
+    }
+
+    /// Ends the game and settles the tiles left on the racks: every player loses the value of
+    /// their own leftover tiles, and a player who went out gains the value of everyone else's.
+    fn finish_game(&mut self) {
+        let leftovers: Vec<isize> = self
+            .players
+            .iter()
+            .map(|player| {
+                player
+                    .hand
+                    .letters
+                    .iter()
+                    .map(|tile| self.language.values.value(*tile) as isize)
+                    .sum()
+            })
+            .collect();
+        let total_leftover: isize = leftovers.iter().sum();
+        let went_out = self.players.iter().position(|p| p.hand.is_empty());
+
+        let mut standings: Vec<Standing> = self
+            .players
+            .iter()
+            .enumerate()
+            .map(|(player, p)| {
+                let going_out_bonus = match went_out {
+                    Some(id) if id == player => total_leftover,
+                    _ => 0,
+                };
+                Standing {
+                    rank: 0,
+                    player,
+                    name: p.name.clone(),
+                    play_score: p.score,
+                    leftover_penalty: leftovers[player],
+                    going_out_bonus,
+                    final_score: p.score - leftovers[player] + going_out_bonus,
                 }
+            })
+            .collect();
+
+        standings.sort_by_key(|s| std::cmp::Reverse(s.final_score));
+        let final_scores: Vec<isize> = standings.iter().map(|s| s.final_score).collect();
+        for standing in &mut standings {
+            // tied players share the best rank between them
+            standing.rank = 1 + final_scores
+                .iter()
+                .filter(|score| **score > standing.final_score)
+                .count();
+            self.players[standing.player].score = standing.final_score;
+        }
 
-                match self.state {
-                    GameState::Turn(player, is_last_round) => {
-                        if is_last_round && self.players.get(player).unwrap().hand.is_empty() {
-                            self.state = GameState::Done;
-                            return Ok(());
-                        }
-                    }
-                    GameState::Done => return Ok(()),
+        self.standings = standings;
+        self.state = GameState::Done;
+    }
+
+    /// The final ranking of the players, best first, or `None` while the game is still going
+    pub fn final_standings(&self) -> Option<&[Standing]> {
+        match self.state {
+            GameState::Done => Some(&self.standings),
+            GameState::Turn(..) => None,
+        }
+    }
+
+    /// Challenges the last play, on behalf of the current player.
+    ///
+    /// Must happen before the current player places any tiles. If the play formed invalid words it
+    /// is withdrawn: the tiles go back to the rack of the player who made it, the tiles they drew
+    /// go back into the bag, and the score is taken away again. Otherwise the challenger pays the
+    /// price set by the `ChallengeRule`.
+    pub fn challenge(&mut self) -> Result<ChallengeOutcome, CrabbleError> {
+        let Validation::Challenge(rule) = self.rules.validation else {
+            return Err(CrabbleError::ChallengesDisabled);
+        };
+        let GameState::Turn(challenger, is_last_round) = self.state else {
+            return Err(CrabbleError::GameOver);
+        };
+        if self.board.provisionary_tiles_count > 0 {
+            return Err(CrabbleError::TilesAlreadyPlaced);
+        }
+        let Some(play) = &self.challengeable else {
+            return Err(CrabbleError::NothingToChallenge);
+        };
+
+        let placed: Vec<Coordinate> = play.placed.iter().map(|(coord, _)| *coord).collect();
+        let invalid = challenge(&self.board, &placed, play.dir, &self.language);
+
+        // withdrawing the play puts the tiles it drew back in the bag, so they must still be on
+        // the rack
+        if !invalid.is_empty() {
+            let hand = &self.players[play.player].hand;
+            for tile in &play.drawn {
+                let needed = play.drawn.iter().filter(|t| *t == tile).count();
+                if hand.count(*tile) < needed {
+                    return Err(CrabbleError::TileNotInHand);
                 }
             }
-            GameState::Done => return Ok(()),
         }
 
-        for coord in self.board.find_range(first_coord, dir) {
-            self.board
-                .get_tile_mut(coord)
-                .unwrap()
-                .as_mut()
-                .unwrap()
-                .is_provisional = false;
+        // nothing can fail from here on, so the challenge goes through
+        self.checkpoint();
+        let play = self.challengeable.take().unwrap();
+
+        let outcome = match invalid.is_empty() {
+            true => ChallengeOutcome::Upheld,
+            false => ChallengeOutcome::Withdrawn(invalid.clone()),
+        };
+        let penalty = match (rule, &outcome) {
+            (ChallengeRule::Penalty(points), ChallengeOutcome::Upheld) => points,
+            _ => 0,
+        };
+        // the record of a withdrawn play no longer counts its points
+        if !invalid.is_empty() {
+            if let Some(TurnRecord {
+                action: TurnAction::Play { withdrawn, .. },
+                score,
+                ..
+            }) = self.history.last_mut()
+            {
+                *withdrawn = true;
+                *score = 0;
+            }
         }
 
-        Ok(())
+        let rack = self.players[challenger].hand.letters.clone();
+        self.history.push(TurnRecord {
+            player: challenger,
+            action: TurnAction::Challenge {
+                challenged: play.player,
+                outcome: outcome.clone(),
+            },
+            score: -penalty,
+            rack_before: rack.clone(),
+            rack_after: rack,
+            hints: std::mem::take(&mut self.hints_this_turn),
+        });
+
+        if invalid.is_empty() {
+            self.players[challenger].score -= penalty;
+
+            if is_last_round {
+                // the play that went out stands, so the game is over
+                self.finish_game();
+            } else if let ChallengeRule::Double = rule {
+                let next_player = (challenger + 1) % self.players.len();
+                self.state = GameState::Turn(next_player, false);
+                self.record_scoreless_turn();
+            }
+            return Ok(outcome);
+        }
+
+        for coord in placed {
+            *self.board.get_tile_mut(coord).unwrap() = None;
+        }
+
+        let player = &mut self.players[play.player];
+        for tile in play.drawn {
+            player.hand.remove(tile).unwrap();
+            self.bag.put(tile, &mut self.rng);
+        }
+        player
+            .hand
+            .letters
+            .extend(play.placed.iter().map(|(_, tile)| *tile));
+        player.score -= play.score;
+
+        // the withdrawn play may have started the last round, which no longer holds
+        if let GameState::Turn(_, was_last_round) = play.state_before {
+            self.state = GameState::Turn(challenger, was_last_round);
+        }
+
+        // the withdrawn play counts as a scoreless turn for the player who made it
+        self.scoreless_turns = play.scoreless_before;
+        self.record_scoreless_turn();
+
+        Ok(outcome)
     }
+}
 
-    fn score_word(&self, word: impl Iterator<Item = Coordinate>, dir: Direction) -> isize {
-        // TODO: count scrabbles
-        let other_dir = dir.flip();
-        let mut total = 0;
+/// The tiles that have been provisionally placed on `board` this turn
+fn provisional_tiles(board: &Board) -> impl Iterator<Item = (Coordinate, BoardTile)> + '_ {
+    board
+        .tiles_with_coordinates()
+        .filter_map(|(coord, tile)| match tile {
+            Some(tile) if tile.is_provisional => Some((coord, tile)),
+            _ => None,
+        })
+}
+
+/// Checks that the provisional tiles on `board` form a single line that connects to the tiles
+/// already on the board, or covers the center square. Returns one of the tiles and the direction
+/// of the word they form.
+pub(crate) fn placed_word(board: &Board) -> Result<(Coordinate, Direction), CrabbleError> {
+    let mut tile_iter = provisional_tiles(board);
 
-        let word_vec: Vec<_> = word.collect();
+    // check that we have played at least a tile
+    let Some((first_coord, _)) = tile_iter.next() else {
+        return Err(CrabbleError::PlayedWordEmpty);
+    };
 
-        total += self.score_range(word_vec.iter().cloned(), false);
+    // check that we have played tiles in a (straight) line
+    let mut axes = (Some(first_coord.x), Some(first_coord.y));
+    for (c, _) in tile_iter {
+        if Some(c.x) != axes.0 {
+            axes.0 = None;
+        }
+        if Some(c.y) != axes.1 {
+            axes.1 = None;
+        }
+    }
+
+    // select direction for gap check
+    let dir = match axes {
+        (Some(_), Some(_)) => {
+            let h = board
+                .find_range(first_coord, Direction::Horizontal)
+                .collect::<Vec<_>>();
+            let v = board
+                .find_range(first_coord, Direction::Vertical)
+                .collect::<Vec<_>>();
+            if h.len() > v.len() {
+                Direction::Horizontal
+            } else {
+                Direction::Vertical
+            }
+        }
+        (Some(_), _) => Direction::Vertical,
+        (_, Some(_)) => Direction::Horizontal,
+        (None, None) => return Err(CrabbleError::InvalidDirection),
+    };
+
+    let coords_vec: Vec<Coordinate> = board.find_range(first_coord, dir).collect();
+
+    // check that there are no provisional tiles in the board
+    // that aren't in this range
+    for (coord, _) in provisional_tiles(board) {
+        if !coords_vec.contains(&coord) {
+            return Err(CrabbleError::ScatteredProvisionalTile);
+        }
+    }
+
+    // check that there are unprovisional tiles in the range
+    // if not, then check that the range is adjacent to at least one
+    // non-provisional tile
+    if board
+        .find_range(first_coord, dir)
+        .all(|coord| board.get_tile(coord).unwrap().is_provisional)
+    {
+        let other_dir = dir.flip();
+        let mut is_adjacent = false;
+
+        // for each adjacent tile in the other direction check that it's a valid word
+        for position in coords_vec {
+            let range = board
+                .find_range(position, other_dir)
+                .map(|coord| board.get_tile(coord).unwrap());
+            let range_vec: Vec<BoardTile> = range.collect();
 
-        for tile in word_vec {
-            let range = self.board.find_range(tile, other_dir);
-            let range_vec: Vec<_> = range.collect();
             if range_vec.len() > 1 {
-                total += self.score_range(range_vec.iter().cloned(), true);
+                is_adjacent = true;
             }
         }
 
-        total
+        if !is_adjacent
+            && !board
+                .find_range(first_coord, dir)
+                .any(|coordinate| board.get_square(coordinate).unwrap().is_center())
+        {
+            return Err(CrabbleError::WordNotAdjacent);
+        }
     }
 
-    fn score_range(&self, word: impl Iterator<Item = Coordinate>, is_adjacent_word: bool) -> isize {
-        let mut total: isize = 0;
-        let mut word_multiplier = 1;
+    Ok((first_coord, dir))
+}
 
-        for letter in word {
-            let tile = self
-                .board
-                .get_tile(letter)
-                .map(|tile| {
-                    let t = tile.tile;
-                    match t.is_joker {
-                        true => HandTile::Joker,
-                        false => HandTile::Letter(t.tile),
-                    }
-                })
-                .unwrap();
+/// Scores the word at `word` and the words the placed tiles form in the other direction, like
+/// `end_turn` awards them
+pub(crate) fn score_word(
+    board: &Board,
+    language: &Language,
+    rules: &Rules,
+    word: impl Iterator<Item = Coordinate>,
+    dir: Direction,
+) -> ScoreBreakdown {
+    let other_dir = dir.flip();
+    let word_vec: Vec<_> = word.collect();
 
-            let mut value = self.language.values.get(tile) as isize;
+    let main_word = score_range(board, language, &word_vec, false);
 
-            if !is_adjacent_word && self.board.get_tile(letter).unwrap().is_provisional {
-                match self.board.get_square(letter).unwrap() {
-                    Square::Empty => (),
-                    Square::CenterSquare => word_multiplier = word_multiplier * 2,
-                    Square::LetterMultiplier(m) => value = value * (m as isize),
-                    Square::WordMultiplier(m) => word_multiplier = word_multiplier * (m as isize),
-                };
-            }
+    let mut cross_words = vec![];
+    for &tile in &word_vec {
+        if !board.get_tile(tile).unwrap().is_provisional {
+            continue;
+        }
+        let range_vec: Vec<_> = board.find_range(tile, other_dir).collect();
+        if range_vec.len() > 1 {
+            cross_words.push(score_range(board, language, &range_vec, true));
+        }
+    }
 
-            total += value;
+    // playing the whole rack in one go earns a bonus
+    let bingo_bonus =
+        (board.provisionary_tiles_count == language.rack_size).then_some(rules.bingo_bonus);
+
+    ScoreBreakdown {
+        main_word,
+        cross_words,
+        bingo_bonus,
+    }
+}
+
+fn score_range(
+    board: &Board,
+    language: &Language,
+    word: &[Coordinate],
+    is_adjacent_word: bool,
+) -> WordScore {
+    let mut letters = vec![];
+    let mut word_multiplier = 1;
+
+    for &coordinate in word {
+        let BoardTile {
+            tile,
+            is_provisional,
+        } = board.get_tile(coordinate).unwrap();
+        let mut letter_multiplier = 1;
+
+        if !is_adjacent_word && is_provisional {
+            match board.get_square(coordinate).unwrap() {
+                Square::Empty | Square::PlainCenterSquare => (),
+                Square::CenterSquare => word_multiplier *= 2,
+                Square::LetterMultiplier(m) => letter_multiplier = m as isize,
+                Square::WordMultiplier(m) => word_multiplier *= m as isize,
+            };
         }
-        total * word_multiplier
+
+        letters.push(LetterScore {
+            coordinate,
+            tile,
+            placed: is_provisional,
+            value: language.values.value(HandTile::from(tile)) as isize,
+            letter_multiplier,
+        });
+    }
+
+    WordScore {
+        word: word_string(board, word),
+        letters,
+        word_multiplier,
     }
 }
 
@@ -341,22 +1295,680 @@ mod tests {
     use std::str::FromStr;
 
     use crate::game::*;
+    use crate::test_utils::*;
+
+    fn challenge_game(rule: ChallengeRule) -> Game {
+        make_game_with_rules(Rules {
+            validation: Validation::Challenge(rule),
+            ..Rules::default()
+        })
+    }
 
     #[test]
     fn scoring_test_1() {
-        let layout = BoardLayout::from_fn((15, 15), crate::standard_board_layout);
-
-        let players = vec![
-            Player::new("Gamer 1".to_string()),
-            Player::new("Player 2".to_string()),
-        ];
-
-        let mut game = Game::new(players, layout, Language::by_name("english").unwrap());
+        let mut game = make_game();
 
-        let a = ASN::from_str("77hcat\na7hs").unwrap();
-        a.run(&mut game, true).unwrap();
+        game.players[0].hand = hand("cat");
+        ASN::from_str("77hcat")
+            .unwrap()
+            .run(&mut game, true)
+            .unwrap();
+        game.players[1].hand = hand("s");
+        ASN::from_str("a7hs").unwrap().run(&mut game, true).unwrap();
 
         let scores: Vec<_> = game.players.iter().map(|p| p.score).collect();
         assert_eq!(scores, [10, 6])
     }
+
+    #[test]
+    fn end_turn_takes_tiles_from_hand() {
+        let mut game = make_game();
+        game.players[0].hand = hand("catxxxx");
+        game.bag = Bag::empty();
+
+        ASN::from_str("77hcat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+
+        assert_eq!(game.players[0].hand.tiles(), hand("xxxx").tiles());
+        assert!(matches!(game.state, GameState::Turn(1, false)));
+    }
+
+    #[test]
+    fn place_tile_needs_tile_in_hand() {
+        let mut game = make_game();
+        game.players[0].hand = hand("cc");
+
+        let tile = Tile {
+            tile: 'c'.into(),
+            is_joker: false,
+        };
+        game.place_tile(tile, Coordinate { x: 7, y: 7 }).unwrap();
+        game.place_tile(tile, Coordinate { x: 8, y: 7 }).unwrap();
+        let err = game
+            .place_tile(tile, Coordinate { x: 9, y: 7 })
+            .unwrap_err();
+        assert_eq!(err, CrabbleError::TileNotInHand);
+    }
+
+    #[test]
+    fn joker_tile_needs_joker_in_hand() {
+        let mut game = make_game();
+
+        game.players[0].hand = hand("cat");
+        let err = ASN::from_str("77h*cat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap_err();
+        assert_eq!(err, CrabbleError::TileNotInHand);
+
+        let mut game = make_game();
+        game.players[0].hand = hand("*at");
+        ASN::from_str("77h*cat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+    }
+
+    #[test]
+    fn challenge_withdraws_invalid_play() {
+        let mut game = challenge_game(ChallengeRule::Double);
+        game.players[0].hand = hand("zzqx");
+
+        ASN::from_str("77hzzqx")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+        assert!(game.players[0].score > 0);
+
+        let outcome = game.challenge().unwrap();
+        assert_eq!(
+            outcome,
+            ChallengeOutcome::Withdrawn(vec!["zzqx".to_string()])
+        );
+        assert_eq!(game.players[0].score, 0);
+        assert_eq!(game.players[0].hand.tiles(), hand("zzqx").tiles());
+        let TurnAction::Play { withdrawn, .. } = game.history()[0].action else {
+            panic!("{:?}", game.history()[0]);
+        };
+        assert!(withdrawn);
+        assert_eq!(game.history()[0].score, 0);
+        assert_eq!(game.get_tile(Coordinate { x: 7, y: 7 }), None);
+        // the challenger keeps their turn
+        assert_eq!(game.state, GameState::Turn(1, false));
+
+        let err = game.challenge().unwrap_err();
+        assert_eq!(err, CrabbleError::NothingToChallenge);
+    }
+
+    #[test]
+    fn failed_challenge_changes_nothing() {
+        let mut game = challenge_game(ChallengeRule::Double);
+        game.players[0].hand = hand("zzqx");
+        ASN::from_str("77hzzqx")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+
+        // the tiles the play drew are gone, so it can't be withdrawn
+        game.players[0].hand = hand("");
+        let undo_steps = game.undo_stack.len();
+        assert_eq!(game.challenge().unwrap_err(), CrabbleError::TileNotInHand);
+        assert_eq!(game.undo_stack.len(), undo_steps);
+        assert_eq!(game.history().len(), 1);
+        assert!(game.challengeable.is_some());
+    }
+
+    #[test]
+    fn double_challenge_loses_turn() {
+        let mut game = challenge_game(ChallengeRule::Double);
+        game.players[0].hand = hand("cat");
+        ASN::from_str("77hcat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+
+        assert_eq!(game.challenge().unwrap(), ChallengeOutcome::Upheld);
+        assert_eq!(game.state, GameState::Turn(0, false));
+        assert_eq!(game.players[0].score, 10);
+    }
+
+    #[test]
+    fn penalty_challenge_costs_points() {
+        let mut game = challenge_game(ChallengeRule::Penalty(5));
+        game.players[0].hand = hand("cat");
+        ASN::from_str("77hcat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+
+        assert_eq!(game.challenge().unwrap(), ChallengeOutcome::Upheld);
+        assert_eq!(game.state, GameState::Turn(1, false));
+        assert_eq!(game.players[1].score, -5);
+    }
+
+    #[test]
+    fn challenge_needs_challenge_rules() {
+        let mut game = make_game();
+        assert_eq!(
+            game.challenge().unwrap_err(),
+            CrabbleError::ChallengesDisabled
+        );
+    }
+
+    #[test]
+    fn exchange_swaps_tiles_with_bag() {
+        let mut game = make_game();
+        game.players[0].hand = hand("iiiuuvv");
+        let bag_size = game.bag.len();
+
+        game.exchange(&hand("uuvv").letters).unwrap();
+
+        let tiles = game.players[0].hand.tiles();
+        assert_eq!(tiles.len(), 7);
+        assert_eq!(&tiles[..3], hand("iii").tiles());
+        assert_eq!(game.bag.len(), bag_size);
+        assert_eq!(game.state, GameState::Turn(1, false));
+    }
+
+    #[test]
+    fn exchange_needs_tiles_in_hand() {
+        let mut game = make_game();
+        game.players[0].hand = hand("iiiuuvv");
+
+        let err = game.exchange(&hand("vvv").letters).unwrap_err();
+        assert_eq!(err, CrabbleError::TileNotInHand);
+        assert_eq!(game.players[0].hand.tiles(), hand("iiiuuvv").tiles());
+    }
+
+    #[test]
+    fn exchange_needs_full_enough_bag() {
+        let mut game = make_game_with_rules(Rules {
+            exchange_min_bag: 200,
+            ..Rules::default()
+        });
+
+        let tiles = game.players[0].hand.tiles()[..1].to_vec();
+        let err = game.exchange(&tiles).unwrap_err();
+        assert_eq!(err, CrabbleError::BagTooSmallToExchange);
+    }
+
+    #[test]
+    fn pass_moves_to_next_player() {
+        let mut game = make_game();
+
+        game.pass().unwrap();
+        assert_eq!(game.state, GameState::Turn(1, false));
+        game.pass().unwrap();
+        assert_eq!(game.state, GameState::Turn(0, false));
+        assert_eq!(game.scoreless_turns(), 2);
+    }
+
+    #[test]
+    fn six_scoreless_turns_end_the_game() {
+        let mut game = make_game();
+
+        for _ in 0..5 {
+            game.pass().unwrap();
+        }
+        game.players[1].hand = hand("cat");
+        ASN::from_str("77hcat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+        assert_eq!(game.scoreless_turns(), 0);
+
+        for _ in 0..5 {
+            game.pass().unwrap();
+        }
+        let tiles = game.players[1].hand.tiles()[..2].to_vec();
+        game.exchange(&tiles).unwrap();
+        assert_eq!(game.state, GameState::Done);
+        assert_eq!(game.pass().unwrap_err(), CrabbleError::GameOver);
+    }
+
+    #[test]
+    fn scoreless_limit_in_rounds() {
+        let mut game = make_game_with_rules(Rules {
+            scoreless_limit: ScorelessLimit::Rounds(2),
+            ..Rules::default()
+        });
+
+        for _ in 0..3 {
+            game.pass().unwrap();
+        }
+        assert_eq!(game.state, GameState::Turn(1, false));
+        game.pass().unwrap();
+        assert_eq!(game.state, GameState::Done);
+    }
+
+    #[test]
+    fn withdrawn_play_is_scoreless() {
+        let mut game = challenge_game(ChallengeRule::Single);
+        game.pass().unwrap();
+        game.players[1].hand = hand("zzqx");
+        ASN::from_str("77hzzqx")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+        assert_eq!(game.scoreless_turns(), 0);
+
+        game.challenge().unwrap();
+        assert_eq!(game.scoreless_turns(), 2);
+    }
+
+    #[test]
+    fn whole_rack_earns_bingo_bonus() {
+        let mut game = make_game_with_rules(Rules {
+            validation: Validation::Off,
+            bingo_bonus: 35,
+            ..Rules::default()
+        });
+        game.players[0].hand = hand("abcdefg");
+        ASN::from_str("47habcdefg")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+
+        // 16 points, doubled by the center square, plus the bonus
+        assert_eq!(game.players[0].score, 32 + 35);
+    }
+
+    #[test]
+    fn no_bingo_bonus_for_partial_rack() {
+        let mut game = make_game();
+        game.players[0].hand = hand("catxxxx");
+        let a = Tile {
+            tile: 'a'.into(),
+            is_joker: false,
+        };
+        let t = Tile {
+            tile: 't'.into(),
+            is_joker: false,
+        };
+        game.place_tile(a, Coordinate { x: 7, y: 7 }).unwrap();
+        game.place_tile(t, Coordinate { x: 8, y: 7 }).unwrap();
+
+        let breakdown = game.end_turn().unwrap();
+        assert_eq!(breakdown.bingo_bonus, None);
+        assert_eq!(breakdown.total(), 4);
+    }
+
+    #[test]
+    fn preview_explains_the_score() {
+        let mut game = make_game_with_rules(Rules {
+            validation: Validation::Off,
+            ..Rules::default()
+        });
+        assert_eq!(game.preview_turn(), Err(CrabbleError::PlayedWordEmpty));
+
+        game.players[0].hand = hand("catxxxx");
+        ASN::from_str("77hcat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+
+        // a joker on a double letter square, with a cross word under each tile of "at"
+        game.players[1].hand = hand("*xyyyyy");
+        game.place_tile(Tile::new('o', true), Coordinate { x: 8, y: 8 })
+            .unwrap();
+        game.place_tile(Tile::new('x', false), Coordinate { x: 9, y: 8 })
+            .unwrap();
+
+        let preview = game.preview_turn().unwrap();
+        assert_eq!(preview.main_word.word, "ox");
+        assert_eq!(preview.main_word.letters[0].letter_multiplier, 2);
+        assert_eq!(preview.main_word.letters[0].total(), 0);
+        assert_eq!(preview.main_word.total(), 8);
+        assert_eq!(
+            preview
+                .cross_words
+                .iter()
+                .map(|word| (word.word.as_str(), word.total()))
+                .collect::<Vec<_>>(),
+            vec![("ao", 1), ("tx", 9)]
+        );
+        assert_eq!(preview.jokers(), vec![Coordinate { x: 8, y: 8 }]);
+        assert_eq!(preview.bingo_bonus, None);
+        assert_eq!(preview.total(), 18);
+
+        // nothing was committed
+        assert_eq!(game.board.provisionary_tiles_count, 2);
+        assert_eq!(game.players[1].score, 0);
+        assert_eq!(game.end_turn().unwrap(), preview);
+    }
+
+    #[test]
+    fn preview_checks_like_end_turn() {
+        let mut game = make_game();
+        game.players[0].hand = hand("qzxxxxx");
+        game.place_tile(Tile::new('q', false), Coordinate { x: 7, y: 7 })
+            .unwrap();
+        game.place_tile(Tile::new('z', false), Coordinate { x: 8, y: 7 })
+            .unwrap();
+
+        let invalid = Err(CrabbleError::InvalidWords(vec!["qz".to_string()]));
+        assert_eq!(game.preview_turn(), invalid);
+        assert_eq!(game.end_turn(), invalid);
+    }
+
+    #[test]
+    fn going_out_settles_leftover_tiles() {
+        let mut game = make_game();
+        game.bag = Bag::empty();
+        game.players[0].hand = hand("cat");
+        game.players[1].hand = hand("qz*");
+
+        ASN::from_str("77hcat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+        assert_eq!(game.state, GameState::Done);
+
+        let standings = game.final_standings().unwrap();
+        assert_eq!(
+            standings[0],
+            Standing {
+                rank: 1,
+                player: 0,
+                name: "Gamer 1".to_string(),
+                play_score: 10,
+                leftover_penalty: 0,
+                going_out_bonus: 20,
+                final_score: 30,
+            }
+        );
+        assert_eq!(standings[1].rank, 2);
+        assert_eq!(standings[1].leftover_penalty, 20);
+        assert_eq!(standings[1].final_score, -20);
+        assert_eq!(game.players[1].score(), -20);
+    }
+
+    #[test]
+    fn tied_players_share_rank() {
+        let mut game = make_game();
+        game.players[0].hand = hand("ab");
+        game.players[1].hand = hand("ba");
+        assert_eq!(game.final_standings(), None);
+
+        for _ in 0..6 {
+            game.pass().unwrap();
+        }
+
+        let standings = game.final_standings().unwrap();
+        assert!(standings.iter().all(|s| s.rank == 1));
+        assert!(standings.iter().all(|s| s.final_score == -4));
+        assert!(standings.iter().all(|s| s.going_out_bonus == 0));
+    }
+
+    #[test]
+    fn going_out_can_be_challenged() {
+        let mut game = challenge_game(ChallengeRule::Double);
+        game.bag = Bag::empty();
+        game.players[0].hand = hand("cat");
+
+        ASN::from_str("77hcat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+        assert_eq!(game.state, GameState::Turn(1, true));
+        assert_eq!(
+            game.exchange(&hand("a").letters).unwrap_err(),
+            CrabbleError::LastPlayWentOut
+        );
+
+        game.pass().unwrap();
+        assert_eq!(game.state, GameState::Done);
+        assert!(game.final_standings().is_some());
+    }
+
+    #[test]
+    fn recall_and_move_provisional_tiles() {
+        let mut game = make_game();
+        game.players[0].hand = hand("cat");
+        let tile = |c: char| Tile {
+            tile: c.into(),
+            is_joker: false,
+        };
+
+        game.place_tile(tile('c'), Coordinate { x: 7, y: 7 })
+            .unwrap();
+        game.place_tile(tile('t'), Coordinate { x: 8, y: 7 })
+            .unwrap();
+        game.place_tile(tile('a'), Coordinate { x: 9, y: 7 })
+            .unwrap();
+        assert_eq!(game.board.provisionary_tiles_count, 3);
+
+        // swap the "a" and the "t" around via an empty square
+        let t = game.recall_tile(Coordinate { x: 8, y: 7 }).unwrap();
+        assert_eq!(t, tile('t'));
+        assert_eq!(game.board.provisionary_tiles_count, 2);
+        game.move_provisional(Coordinate { x: 9, y: 7 }, Coordinate { x: 8, y: 7 })
+            .unwrap();
+        game.place_tile(t, Coordinate { x: 9, y: 7 }).unwrap();
+
+        assert_eq!(
+            game.recall_tile(Coordinate { x: 3, y: 3 }).unwrap_err(),
+            CrabbleError::NoProvisionalTile
+        );
+        assert_eq!(
+            game.move_provisional(Coordinate { x: 7, y: 7 }, Coordinate { x: 8, y: 7 })
+                .unwrap_err(),
+            CrabbleError::TileOccupied
+        );
+
+        game.end_turn().unwrap();
+        assert_eq!(game.players[0].score, 10);
+        assert_eq!(
+            game.recall_tile(Coordinate { x: 7, y: 7 }).unwrap_err(),
+            CrabbleError::NoProvisionalTile
+        );
+    }
+
+    #[test]
+    fn recall_all_clears_the_board() {
+        let mut game = make_game();
+        game.players[0].hand = hand("cat");
+        let err = ASN::from_str("00hcat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap_err();
+        assert_eq!(err, CrabbleError::WordNotAdjacent);
+
+        assert_eq!(game.board.provisionary_tiles_count, 0);
+        assert_eq!(game.get_tile(Coordinate { x: 0, y: 0 }), None);
+        assert_eq!(game.recall_all(), vec![]);
+
+        ASN::from_str("77hcat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+    }
+
+    #[test]
+    fn history_records_every_turn() {
+        let mut game = challenge_game(ChallengeRule::Penalty(10));
+        game.players[0].hand = hand("catxxxx");
+        ASN::from_str("77hcat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+        game.challenge().unwrap();
+        game.players[1].hand = hand("iiiuuvv");
+        game.exchange(&hand("uuvv").letters).unwrap();
+        game.pass().unwrap();
+
+        let history = game.history();
+        assert_eq!(history.len(), 4);
+
+        let play = &history[0];
+        assert_eq!(play.player, 0);
+        assert_eq!(play.score, 10);
+        assert_eq!(play.rack_before, hand("catxxxx").letters);
+        assert_eq!(&play.rack_after[..4], hand("xxxx").tiles());
+        let TurnAction::Play { tiles, words, .. } = &play.action else {
+            panic!("expected a play, got {:?}", play.action);
+        };
+        assert_eq!(words, &["cat".to_string()]);
+        assert_eq!(tiles[0], (Coordinate { x: 7, y: 7 }, Tile::new('c', false)));
+
+        assert_eq!(
+            history[1].action,
+            TurnAction::Challenge {
+                challenged: 0,
+                outcome: ChallengeOutcome::Upheld
+            }
+        );
+        assert_eq!(history[1].score, -10);
+
+        assert_eq!(
+            history[2].action,
+            TurnAction::Exchange {
+                tiles: hand("uuvv").letters
+            }
+        );
+        assert_eq!(history[2].player, 1);
+        assert_eq!(history[3].action, TurnAction::Pass);
+        assert_eq!(history[3].player, 0);
+    }
+
+    #[test]
+    fn undo_and_redo_restore_positions() {
+        let mut game = make_game();
+        assert_eq!(game.undo().unwrap_err(), CrabbleError::NothingToUndo);
+
+        let start = game.clone();
+        game.players[0].hand = hand("catxxxx");
+        let rigged = game.clone();
+        ASN::from_str("77hcat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+        let after_play = game.clone();
+        let tiles = game.players[1].hand.tiles()[..3].to_vec();
+        game.exchange(&tiles).unwrap();
+        let after_exchange = game.clone();
+
+        let same = |a: &Game, b: &Game| {
+            a.board == b.board
+                && a.bag == b.bag
+                && a.players == b.players
+                && a.state == b.state
+                && a.history == b.history
+        };
+
+        game.undo().unwrap();
+        assert!(same(&game, &after_play));
+        game.undo().unwrap();
+        assert!(same(&game, &rigged));
+        assert!(!same(&game, &start));
+        assert_eq!(game.undo().unwrap_err(), CrabbleError::NothingToUndo);
+
+        game.redo().unwrap();
+        assert!(same(&game, &after_play));
+        game.redo().unwrap();
+        assert!(same(&game, &after_exchange));
+        assert_eq!(game.redo().unwrap_err(), CrabbleError::NothingToRedo);
+
+        // a copy to try moves on starts its own history
+        let mut copy = game.without_undo_history();
+        assert!(same(&copy, &game));
+        assert_eq!(copy.undo().unwrap_err(), CrabbleError::NothingToUndo);
+
+        // playing something else from an earlier position drops the redo steps
+        game.undo().unwrap();
+        game.pass().unwrap();
+        assert_eq!(game.redo().unwrap_err(), CrabbleError::NothingToRedo);
+        assert_eq!(game.history().last().unwrap().action, TurnAction::Pass);
+    }
+
+    #[test]
+    fn undo_takes_back_provisional_tiles() {
+        let mut game = make_game();
+        game.players[0].hand = hand("catxxxx");
+        ASN::from_str("77hcat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+
+        game.players[1].hand = hand("s");
+        game.place_tile(Tile::new('s', false), Coordinate { x: 10, y: 7 })
+            .unwrap();
+        game.undo().unwrap();
+
+        assert_eq!(game.board.provisionary_tiles_count, 0);
+        assert_eq!(game.get_tile(Coordinate { x: 7, y: 7 }), None);
+        assert_eq!(game.get_tile(Coordinate { x: 10, y: 7 }), None);
+        assert_eq!(game.players[0].hand.tiles(), hand("catxxxx").tiles());
+        assert_eq!(game.state, GameState::Turn(0, false));
+    }
+
+    #[test]
+    fn same_seed_deals_same_tiles() {
+        let mut a = seeded_game(42);
+        let mut b = seeded_game(42);
+        assert_eq!(a.seed(), 42);
+        assert_eq!(a.players, b.players);
+        assert_ne!(a.players, seeded_game(43).players);
+
+        // exchanges put tiles back at random spots, which have to line up as well
+        for _ in 0..3 {
+            let tiles = a.players[a.current_player().unwrap()].hand.tiles()[..3].to_vec();
+            a.exchange(&tiles).unwrap();
+            b.exchange(&tiles).unwrap();
+        }
+        assert_eq!(a.players, b.players);
+        assert_eq!(a.bag, b.bag);
+    }
+
+    #[test]
+    fn hints_are_counted_in_the_history() {
+        let mut game = make_game();
+        game.players[0].hand = hand("cat*");
+
+        let hints = game.hints(3).unwrap();
+        assert_eq!(hints.len(), 3);
+        assert!(hints.windows(2).all(|w| w[0].score >= w[1].score));
+        let best = &hints[0];
+        assert_eq!(best.score, best.play.score());
+        assert_eq!(best.words, best.play.words);
+        assert_eq!(best.leave.len(), 4 - best.play.tiles.len());
+        game.hints(1).unwrap();
+        assert_eq!(game.hints_used(0), 2);
+
+        // the hint can be submitted as it is
+        let asn = best.asn.clone();
+        ASN::from_str(&asn).unwrap().run(&mut game, false).unwrap();
+        let record = game.history().last().unwrap();
+        assert_eq!(record.hints, 2);
+        assert_eq!(record.score, best.score);
+        assert_eq!(game.hints_used(0), 2);
+        assert_eq!(game.hints_used(1), 0);
+    }
+
+    #[test]
+    fn hints_follow_the_rules() {
+        let mut game = make_game_with_rules(Rules {
+            hints: HintPolicy::Off,
+            ..Rules::default()
+        });
+        assert_eq!(game.hints(1).unwrap_err(), CrabbleError::HintsDisabled);
+
+        let mut game = make_game_with_rules(Rules {
+            hints: HintPolicy::Limited(2),
+            ..Rules::default()
+        });
+        game.hints(1).unwrap();
+        game.pass().unwrap();
+        game.pass().unwrap();
+        game.hints(5).unwrap();
+        assert_eq!(game.hints(1).unwrap_err(), CrabbleError::NoHintsLeft);
+
+        // other players still have theirs
+        game.pass().unwrap();
+        assert!(game.hints(1).is_ok());
+        game.undo().unwrap();
+        assert_eq!(game.hints_used(0), 2);
+    }
 }
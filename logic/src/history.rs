@@ -0,0 +1,41 @@
+use crate::{rules::ChallengeOutcome, score::ScoreBreakdown, Coordinate, HandTile, Tile};
+
+/// A single committed turn, as kept in `Game::history`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TurnRecord {
+    /// Index of the player who took the turn
+    pub player: usize,
+    pub action: TurnAction,
+    /// How much the player's score changed because of this turn
+    pub score: isize,
+    pub rack_before: Vec<HandTile>,
+    pub rack_after: Vec<HandTile>,
+    /// How many times the player asked for hints during the turn
+    pub hints: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TurnAction {
+    /// Tiles were placed on the board
+    Play {
+        tiles: Vec<(Coordinate, Tile)>,
+        /// The words formed by the play, the main word first
+        words: Vec<String>,
+        breakdown: ScoreBreakdown,
+        /// Whether a challenge took the play back off the board. The record then scores nothing.
+        withdrawn: bool,
+    },
+    /// Tiles were swapped with the bag
+    Exchange {
+        tiles: Vec<HandTile>,
+    },
+    Pass,
+    /// The play of `challenged` was challenged. If it was withdrawn, their score and rack were
+    /// restored to what they were before that play, and its record is marked as withdrawn.
+    Challenge {
+        challenged: usize,
+        outcome: ChallengeOutcome,
+    },
+}
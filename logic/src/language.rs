@@ -1,27 +1,383 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    fs,
+    path::Path,
+    sync::{Arc, RwLock},
+};
 
-use crate::{CrabbleError, HandTile};
+use crate::{
+    dictionary::{Dictionary, FstWords},
+    normalize::{can_spell, fold, normalize, parse_alphabet, parse_folding},
+    CrabbleError, HandTile, Letter,
+};
 
-static LANGUAGE_DATA: &[(&str, &str)] = &[
-    ("english", include_str!("../../data/english/letters.csv")),
-    ("dutch", include_str!("../../data/dutch/letters.csv")),
+struct LanguageData {
+    name: &'static str,
+    letters: &'static str,
+    metadata: &'static str,
+    /// `words.txt` and every `words-<edition>.txt`, turned into automata by the build script. The
+    /// standard lexicon comes first.
+    lexicons: &'static [(&'static str, &'static [u8])],
+}
+
+/// The name of the lexicon read from `words.txt`
+pub const STANDARD_LEXICON: &str = "standard";
+
+/// The name of the edition with only everyday words, read from `words-common.txt`. Beginner bots
+/// stick to it.
+pub const COMMON_LEXICON: &str = "common";
+
+static LANGUAGE_DATA: [LanguageData; 6] = [
+    LanguageData {
+        name: "english",
+        letters: include_str!("../../data/english/letters.csv"),
+        metadata: include_str!("../../data/english/metadata.txt"),
+        lexicons: &[
+            (
+                STANDARD_LEXICON,
+                include_bytes!(concat!(env!("OUT_DIR"), "/english.fst")),
+            ),
+            (
+                COMMON_LEXICON,
+                include_bytes!(concat!(env!("OUT_DIR"), "/english-common.fst")),
+            ),
+        ],
+    },
+    LanguageData {
+        name: "dutch",
+        letters: include_str!("../../data/dutch/letters.csv"),
+        metadata: include_str!("../../data/dutch/metadata.txt"),
+        lexicons: &[
+            (
+                STANDARD_LEXICON,
+                include_bytes!(concat!(env!("OUT_DIR"), "/dutch.fst")),
+            ),
+            (
+                COMMON_LEXICON,
+                include_bytes!(concat!(env!("OUT_DIR"), "/dutch-common.fst")),
+            ),
+        ],
+    },
+    LanguageData {
+        name: "german",
+        letters: include_str!("../../data/german/letters.csv"),
+        metadata: include_str!("../../data/german/metadata.txt"),
+        lexicons: &[(
+            STANDARD_LEXICON,
+            include_bytes!(concat!(env!("OUT_DIR"), "/german.fst")),
+        )],
+    },
+    LanguageData {
+        name: "french",
+        letters: include_str!("../../data/french/letters.csv"),
+        metadata: include_str!("../../data/french/metadata.txt"),
+        lexicons: &[(
+            STANDARD_LEXICON,
+            include_bytes!(concat!(env!("OUT_DIR"), "/french.fst")),
+        )],
+    },
+    LanguageData {
+        name: "spanish",
+        letters: include_str!("../../data/spanish/letters.csv"),
+        metadata: include_str!("../../data/spanish/metadata.txt"),
+        lexicons: &[(
+            STANDARD_LEXICON,
+            include_bytes!(concat!(env!("OUT_DIR"), "/spanish.fst")),
+        )],
+    },
+    LanguageData {
+        name: "italian",
+        letters: include_str!("../../data/italian/letters.csv"),
+        metadata: include_str!("../../data/italian/metadata.txt"),
+        lexicons: &[(
+            STANDARD_LEXICON,
+            include_bytes!(concat!(env!("OUT_DIR"), "/italian.fst")),
+        )],
+    },
 ];
 
-#[derive(Debug)]
+/// Languages loaded with `Language::from_dir`. These are looked up before the bundled languages,
+/// so a directory can replace a bundled language with a house version.
+static LOADED: RwLock<Vec<Language>> = RwLock::new(Vec::new());
+
+/// A language that can be passed to `Language::by_name`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LanguageEntry {
+    pub name: String,
+    pub display_name: String,
+    /// The names of the lexicons that can be chosen from
+    pub lexicons: Vec<String>,
+}
+
+/// Lists every language that can be played: the bundled ones followed by the ones loaded from
+/// disk
+pub fn available() -> Vec<LanguageEntry> {
+    let loaded = LOADED.read().unwrap();
+
+    let bundled = LANGUAGE_DATA
+        .iter()
+        .filter(|data| !loaded.iter().any(|language| language.name == data.name))
+        .map(|data| LanguageEntry {
+            name: data.name.into(),
+            display_name: Metadata::parse(data.metadata)
+                .ok()
+                .and_then(|metadata| metadata.display_name)
+                .unwrap_or_else(|| data.name.into()),
+            lexicons: data
+                .lexicons
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+        });
+    let loaded = loaded.iter().map(|language| LanguageEntry {
+        name: language.name.clone(),
+        display_name: language.display_name.clone(),
+        lexicons: language
+            .lexicons
+            .iter()
+            .map(|lexicon| lexicon.name().into())
+            .collect(),
+    });
+
+    bundled.chain(loaded).collect()
+}
+
+/// Loads every subdirectory of `dir` that has a `letters.csv` with `Language::from_dir`, and
+/// returns the names of the loaded languages
+pub fn load_languages(dir: impl AsRef<Path>) -> Result<Vec<String>, CrabbleError> {
+    let dir = dir.as_ref();
+    let entries = fs::read_dir(dir).map_err(|e| file_error(dir, e))?;
+
+    let mut dirs = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| file_error(dir, e))?.path();
+        if path.join("letters.csv").is_file() {
+            dirs.push(path);
+        }
+    }
+    dirs.sort();
+
+    dirs.iter()
+        .map(|dir| Language::from_dir(dir).map(|language| language.name))
+        .collect()
+}
+
+fn file_error(path: &Path, error: impl Display) -> CrabbleError {
+    CrabbleError::LanguageFile(format!("{}: {error}", path.display()))
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Language {
     pub name: String,
+    /// The name to show to players, in the language itself
+    pub display_name: String,
     pub distribution: Distribution,
     pub values: LetterValues,
+    /// The number of tiles on a full rack
+    pub rack_size: usize,
+    /// Every edition of the word list, the standard one first
+    pub lexicons: Vec<Lexicon>,
+    /// The names of the lexicons words are checked against
+    selected: Vec<String>,
+    /// Letters that are played with the tile of another letter, see `Metadata::fold`
+    folding: Vec<(String, String)>,
+    /// See `Metadata::complete_lexicon`
+    complete_lexicon: bool,
 }
 
 impl Language {
+    /// Finds a language loaded with `from_dir`, or else one of the bundled languages
     pub fn by_name(lang: &str) -> Result<Self, CrabbleError> {
-        let Some((_, data)) = LANGUAGE_DATA.iter().find(|(l, _)| *l == lang) else {
+        let loaded = LOADED.read().unwrap();
+        if let Some(language) = loaded.iter().find(|language| language.name == lang) {
+            return Ok(language.clone());
+        }
+        drop(loaded);
+
+        let Some(data) = LANGUAGE_DATA.iter().find(|data| data.name == lang) else {
             return Err(CrabbleError::InvalidLanguage);
         };
-        Language::parse_csv(lang, data)
+        Ok(Language::parse_csv(lang, data.letters)?
+            .with_metadata(Metadata::parse(data.metadata)?)?
+            .with_lexicons(
+                data.lexicons
+                    .iter()
+                    .map(|(name, _)| Lexicon::find(lang, name))
+                    .collect::<Result<_, _>>()?,
+            ))
+    }
+
+    /// Loads the language in `dir`, which is named after the directory. The directory needs a
+    /// `letters.csv` and a `words.txt` with one word per line, and can have a `metadata.txt`.
+    /// Any `words-<name>.txt` next to them is another edition of the lexicon called `<name>`.
+    /// Words that can't be spelled with the alphabet are left out of the lexicons.
+    ///
+    /// The language is registered, so `by_name` and `available` know about it afterwards.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, CrabbleError> {
+        let dir = dir.as_ref();
+        let read = |file: &str| {
+            let path = dir.join(file);
+            fs::read_to_string(&path).map_err(|e| file_error(&path, e))
+        };
+
+        let name = dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| file_error(dir, "not a valid language name"))?;
+        let metadata = match dir.join("metadata.txt").exists() {
+            true => Metadata::parse(&read("metadata.txt")?)?,
+            false => Metadata::default(),
+        };
+        let alphabet = metadata.alphabet.clone();
+
+        let language = Language::parse_csv(name, &read("letters.csv")?)?.with_metadata(metadata)?;
+        let alphabet = alphabet.unwrap_or_else(|| language.letters().collect());
+
+        let mut editions = vec![(STANDARD_LEXICON.to_string(), "words.txt".to_string())];
+        let mut others = Vec::new();
+        for entry in fs::read_dir(dir).map_err(|e| file_error(dir, e))? {
+            let file = entry.map_err(|e| file_error(dir, e))?.file_name();
+            let Some(file) = file.to_str() else {
+                continue;
+            };
+            if let Some(edition) = file
+                .strip_prefix("words-")
+                .and_then(|file| file.strip_suffix(".txt"))
+            {
+                others.push((edition.to_string(), file.to_string()));
+            }
+        }
+        others.sort();
+        editions.extend(others);
+
+        let mut lexicons = Vec::new();
+        for (edition, file) in editions {
+            let words = read(&file)?;
+            lexicons.push(Lexicon::new(
+                name,
+                &edition,
+                words
+                    .lines()
+                    .map(|word| language.normalize(word.trim()))
+                    .filter(|word| can_spell(word, &alphabet)),
+            ));
+        }
+        let language = language.with_lexicons(lexicons);
+
+        let mut loaded = LOADED.write().unwrap();
+        loaded.retain(|loaded| loaded.name != language.name);
+        loaded.push(language.clone());
+
+        Ok(language)
+    }
+
+    fn with_metadata(mut self, metadata: Metadata) -> Result<Self, CrabbleError> {
+        if let Some(display_name) = metadata.display_name {
+            self.display_name = display_name;
+        }
+        if let Some(rack_size) = metadata.rack_size {
+            self.rack_size = rack_size;
+        }
+        if let Some(folding) = metadata.fold {
+            self.folding = folding;
+        }
+        if let Some(complete) = metadata.complete_lexicon {
+            self.complete_lexicon = complete;
+        }
+        if let Some(alphabet) = metadata.alphabet {
+            if let Some(letter) = self.letters().find(|letter| !alphabet.contains(letter)) {
+                return Err(CrabbleError::LanguageFile(format!(
+                    "{}: tile {letter} is not in the alphabet",
+                    self.name
+                )));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Sets the editions of the lexicon and selects the first one
+    pub fn with_lexicons(mut self, lexicons: Vec<Lexicon>) -> Self {
+        self.selected = lexicons
+            .iter()
+            .take(1)
+            .map(|lexicon| lexicon.name().into())
+            .collect();
+        self.lexicons = lexicons;
+        self
+    }
+
+    /// Chooses the lexicons words are checked against. When more than one is chosen, a word is
+    /// valid if any of them contains it.
+    pub fn select_lexicons(&mut self, names: &[&str]) -> Result<(), CrabbleError> {
+        if names.is_empty() {
+            return Err(CrabbleError::NoLexiconSelected);
+        }
+        if let Some(unknown) = names
+            .iter()
+            .find(|name| !self.lexicons.iter().any(|lexicon| lexicon.name() == **name))
+        {
+            return Err(CrabbleError::UnknownLexicon(unknown.to_string()));
+        }
+
+        self.selected = self
+            .lexicons
+            .iter()
+            .map(|lexicon| lexicon.name())
+            .filter(|name| names.contains(name))
+            .map(String::from)
+            .collect();
+        Ok(())
+    }
+
+    /// Whether the selected lexicons have every word of the language. Plays are checked against
+    /// them either way, so with a starter list valid words can be refused, and frontends can say
+    /// so. See `Metadata::complete_lexicon`.
+    pub fn has_complete_lexicon(&self) -> bool {
+        self.complete_lexicon && self.selected_lexicons().next().is_some()
+    }
+
+    /// The lexicons chosen with `select_lexicons`
+    pub fn selected_lexicons(&self) -> impl Iterator<Item = &Lexicon> + '_ {
+        self.lexicons
+            .iter()
+            .filter(|lexicon| self.selected.contains(&lexicon.name))
     }
 
+    /// Brings `text` into the form the tiles and lexicons of this language are in: case folded,
+    /// composed to NFC, and with the letters of `Metadata::fold` replaced
+    pub fn normalize(&self, text: &str) -> String {
+        fold(text, &self.folding)
+    }
+
+    /// The letter of the tile written as `symbol`, in any case or encoding
+    pub fn letter(&self, symbol: &str) -> Result<Letter, CrabbleError> {
+        if let Some(letter) = self.letters().find(|letter| letter.as_str() == symbol) {
+            return Ok(letter);
+        }
+
+        let normalized = self.normalize(symbol);
+        self.letters()
+            .find(|letter| letter.as_str() == normalized)
+            .ok_or_else(|| CrabbleError::UnknownLetter(symbol.into()))
+    }
+
+    /// Every way of spelling `word` with the tiles of this language, see `spellings`
+    pub fn spellings(&self, word: &str) -> Vec<Vec<Letter>> {
+        spellings(word, &self.letters().collect::<Vec<_>>())
+    }
+
+    /// The letters on the tiles, without the joker
+    pub(crate) fn letters(&self) -> impl Iterator<Item = Letter> + '_ {
+        self.distribution.iter().filter_map(|(tile, _)| match tile {
+            HandTile::Letter(letter) => Some(letter),
+            HandTile::Joker => None,
+        })
+    }
+
+    /// Reads the tiles of a language. It has no lexicon yet, see `with_lexicons`.
     pub fn parse_csv(name: &str, csv: &str) -> Result<Self, CrabbleError> {
         let mut vec = Vec::new();
         let mut values = HashMap::new();
@@ -46,12 +402,9 @@ impl Language {
             let tile = if letter == " " {
                 HandTile::Joker
             } else {
-                let mut chars = letter.chars();
-                let first = chars.next().ok_or(CrabbleError::InvalidLanguage)?;
-                if chars.next().is_some() {
-                    return Err(CrabbleError::InvalidLanguage);
-                }
-                HandTile::Letter(first)
+                let letter =
+                    Letter::new(&normalize(letter)).ok_or(CrabbleError::InvalidLanguage)?;
+                HandTile::Letter(letter)
             };
 
             let amount = amount.parse().map_err(|_| CrabbleError::InvalidLanguage)?;
@@ -63,22 +416,374 @@ impl Language {
 
         Ok(Language {
             name: name.into(),
+            display_name: name.into(),
             distribution: Distribution(vec),
             values: LetterValues(values),
+            rack_size: 7,
+            lexicons: vec![],
+            selected: vec![],
+            folding: vec![],
+            complete_lexicon: true,
         })
     }
 }
 
-#[derive(Debug)]
+/// Words are looked up in the selected lexicons, a word is valid if any of them has it
+impl Dictionary for Language {
+    /// A node for each selected lexicon, or `None` where that lexicon has no word with the prefix
+    type Node = Vec<Option<<Lexicon as Dictionary>::Node>>;
+
+    fn root(&self) -> Self::Node {
+        self.selected_lexicons()
+            .map(|lexicon| Some(lexicon.root()))
+            .collect()
+    }
+
+    fn is_word(&self, node: &Self::Node) -> bool {
+        self.selected_lexicons()
+            .zip(node)
+            .any(|(lexicon, node)| node.as_ref().is_some_and(|node| lexicon.is_word(node)))
+    }
+
+    fn children(&self, node: &Self::Node) -> Vec<(char, Self::Node)> {
+        let mut children: Vec<(char, Self::Node)> = Vec::new();
+        for (i, (lexicon, node)) in self.selected_lexicons().zip(node).enumerate() {
+            let Some(node) = node else {
+                continue;
+            };
+            for (letter, child) in lexicon.children(node) {
+                match children.iter_mut().find(|(c, _)| *c == letter) {
+                    Some((_, nodes)) => nodes[i] = Some(child),
+                    None => {
+                        let mut nodes = vec![None; self.selected.len()];
+                        nodes[i] = Some(child);
+                        children.push((letter, nodes));
+                    }
+                }
+            }
+        }
+        children.sort_by_key(|(letter, _)| *letter);
+        children
+    }
+
+    fn child(&self, node: &Self::Node, letter: char) -> Option<Self::Node> {
+        let child: Self::Node = self
+            .selected_lexicons()
+            .zip(node)
+            .map(|(lexicon, node)| node.as_ref().and_then(|node| lexicon.child(node, letter)))
+            .collect();
+        child.iter().any(Option::is_some).then_some(child)
+    }
+
+    fn follow(&self, node: &Self::Node, letters: &str) -> Option<Self::Node> {
+        let child: Self::Node = self
+            .selected_lexicons()
+            .zip(node)
+            .map(|(lexicon, node)| node.as_ref().and_then(|node| lexicon.follow(node, letters)))
+            .collect();
+        child.iter().any(Option::is_some).then_some(child)
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.selected_lexicons()
+            .any(|lexicon| lexicon.contains(word))
+    }
+}
+
+/// Every way of splitting `word` into the letters of `tiles`. There can be more than one when some
+/// tiles have more than one letter: with the Spanish tiles, "llama" is both LL-A-M-A and
+/// L-L-A-M-A. Words can be played either way.
+pub fn spellings(word: &str, tiles: &[Letter]) -> Vec<Vec<Letter>> {
+    if word.is_empty() {
+        return vec![vec![]];
+    }
+
+    let mut spellings = Vec::new();
+    for tile in tiles {
+        if let Some(rest) = word.strip_prefix(tile.as_str()) {
+            for mut spelling in self::spellings(rest, tiles) {
+                spelling.insert(0, *tile);
+                spellings.push(spelling);
+            }
+        }
+    }
+    spellings
+}
+
+/// The optional `metadata.txt` of a language, with one `key = value` setting per line:
+///
+/// ```text
+/// # lines starting with a hash are comments
+/// display_name = Nederlands
+/// alphabet = abcdefghijklmnopqrstuvwxyz
+/// rack_size = 7
+/// complete_lexicon = false
+/// ```
+///
+/// An alphabet with tiles of more than one letter separates them with spaces, like
+/// `alphabet = a b c ch d e ... l ll m n ñ o p q r rr s t u v x y z`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metadata {
+    pub display_name: Option<String>,
+    /// The letters of the language, all tiles must be one of them
+    pub alphabet: Option<Vec<Letter>>,
+    /// Letters that have no tile of their own but are played with another one, like
+    /// `fold = á:a é:e í:i ó:o ú:u ü:u` in Spanish. Letters that do have a tile, like the Ñ, are
+    /// left alone. Words and tiles are case folded and composed to NFC in any case.
+    pub fold: Option<Vec<(String, String)>>,
+    pub rack_size: Option<usize>,
+    /// Whether the word lists have every word of the language, which they do unless this says
+    /// `false` for a starter list of everyday words. `Validation::Auto` and challenges check
+    /// words against the lists all the same, so they always agree on a word.
+    pub complete_lexicon: Option<bool>,
+}
+
+impl Metadata {
+    pub fn parse(text: &str) -> Result<Self, CrabbleError> {
+        let mut metadata = Metadata::default();
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(CrabbleError::LanguageFile(format!(
+                    "expected key = value: {line}"
+                )));
+            };
+            let value = value.trim();
+
+            match key.trim() {
+                "display_name" => metadata.display_name = Some(value.into()),
+                "alphabet" => {
+                    let alphabet = parse_alphabet(value)
+                        .iter()
+                        .map(|letter| {
+                            Letter::new(letter).ok_or_else(|| {
+                                CrabbleError::LanguageFile(format!("letter is too long: {letter}"))
+                            })
+                        })
+                        .collect::<Result<_, _>>()?;
+                    metadata.alphabet = Some(alphabet);
+                }
+                "fold" => {
+                    let folding = parse_folding(value).map_err(CrabbleError::LanguageFile)?;
+                    metadata.fold = Some(folding);
+                }
+                "rack_size" => {
+                    let rack_size =
+                        value.parse().ok().filter(|size| *size > 0).ok_or_else(|| {
+                            CrabbleError::LanguageFile(format!("invalid rack size: {value}"))
+                        })?;
+                    metadata.rack_size = Some(rack_size);
+                }
+                "complete_lexicon" => {
+                    let complete = value.parse().map_err(|_| {
+                        CrabbleError::LanguageFile(format!("expected true or false: {value}"))
+                    })?;
+                    metadata.complete_lexicon = Some(complete);
+                }
+                key => {
+                    return Err(CrabbleError::LanguageFile(format!(
+                        "unknown metadata key: {key}"
+                    )))
+                }
+            }
+        }
+
+        Ok(metadata)
+    }
+}
+
+/// A list of the words that may be played. Clones share the same list.
+#[derive(Clone)]
+pub struct Lexicon {
+    language: String,
+    name: String,
+    words: Arc<FstWords>,
+}
+
+impl Lexicon {
+    /// Reads a word list with one word per line
+    pub fn parse(language: &str, name: &str, words: &str) -> Self {
+        Lexicon::new(language, name, words.lines())
+    }
+
+    /// The words are case folded and composed to NFC, like the tiles
+    pub fn new(
+        language: &str,
+        name: &str,
+        words: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Self {
+        let words = words
+            .into_iter()
+            .map(|word| normalize(word.as_ref().trim()))
+            .filter(|word| !word.is_empty());
+
+        Lexicon {
+            language: language.into(),
+            name: name.into(),
+            words: Arc::new(FstWords::new(words)),
+        }
+    }
+
+    /// The lexicon called `name` of a language loaded with `Language::from_dir`, or else of the
+    /// bundled language `lang`
+    pub fn find(lang: &str, name: &str) -> Result<Self, CrabbleError> {
+        let loaded = LOADED.read().unwrap();
+        if let Some(language) = loaded.iter().find(|language| language.name == lang) {
+            return language
+                .lexicons
+                .iter()
+                .find(|lexicon| lexicon.name == name)
+                .cloned()
+                .ok_or_else(|| CrabbleError::UnknownLexicon(name.into()));
+        }
+
+        let Some(data) = LANGUAGE_DATA.iter().find(|data| data.name == lang) else {
+            return Err(CrabbleError::InvalidLanguage);
+        };
+        let Some((name, words)) = data.lexicons.iter().find(|(edition, _)| *edition == name) else {
+            return Err(CrabbleError::UnknownLexicon(name.into()));
+        };
+        // the automaton is read in place, so this is cheap
+        Ok(Lexicon {
+            language: data.name.into(),
+            name: name.to_string(),
+            words: Arc::new(FstWords::from_bytes(words).unwrap()),
+        })
+    }
+
+    /// The language this lexicon belongs to
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+impl Dictionary for Lexicon {
+    type Node = <FstWords as Dictionary>::Node;
+
+    fn root(&self) -> Self::Node {
+        self.words.root()
+    }
+
+    fn is_word(&self, node: &Self::Node) -> bool {
+        self.words.is_word(node)
+    }
+
+    fn children(&self, node: &Self::Node) -> Vec<(char, Self::Node)> {
+        self.words.children(node)
+    }
+
+    fn child(&self, node: &Self::Node, letter: char) -> Option<Self::Node> {
+        self.words.child(node, letter)
+    }
+
+    fn follow(&self, node: &Self::Node, letters: &str) -> Option<Self::Node> {
+        self.words.follow(node, letters)
+    }
+
+    fn walk(&self, prefix: &str) -> Option<Self::Node> {
+        self.words.walk(prefix)
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+}
+
+/// Only prints the names, the word list itself is far too long to be useful
+impl Debug for Lexicon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Lexicon")
+            .field("language", &self.language)
+            .field("name", &self.name)
+            .field("words", &self.words.len())
+            .finish()
+    }
+}
+
+/// How a lexicon is saved: only its names, loading looks the words up again
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct LexiconId<'a> {
+    language: &'a str,
+    name: &'a str,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Lexicon {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        LexiconId {
+            language: &self.language,
+            name: &self.name,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Lexicon {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = LexiconId::deserialize(deserializer)?;
+        Lexicon::find(id.language, id.name).map_err(|_| {
+            serde::de::Error::custom(format!("unknown lexicon {} of {}", id.name, id.language))
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct LetterValues(HashMap<HandTile, usize>);
 
+/// Saved sorted by tile, so the same language always gives the same JSON
+#[cfg(feature = "serde")]
+impl serde::Serialize for LetterValues {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let sorted: std::collections::BTreeMap<String, usize> = self
+            .0
+            .iter()
+            .map(|(tile, value)| (tile.to_string(), *value))
+            .collect();
+        sorted.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for LetterValues {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        HashMap::deserialize(deserializer).map(LetterValues)
+    }
+}
+
 impl LetterValues {
-    pub fn get(&self, tile: HandTile) -> usize {
-        self.0[&tile]
+    pub fn get(&self, tile: HandTile) -> Result<usize, CrabbleError> {
+        self.0
+            .get(&tile)
+            .copied()
+            .ok_or_else(|| CrabbleError::UnknownLetter(tile.to_string()))
+    }
+
+    /// Like `get`, but a tile that isn't part of the language is worth nothing
+    pub fn value(&self, tile: HandTile) -> usize {
+        self.get(tile).unwrap_or(0)
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Distribution(Vec<(HandTile, usize)>);
 
 impl Distribution {
@@ -95,3 +800,286 @@ impl Display for Distribution {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// Writes a language directory called `name` in a fresh temporary directory
+    fn write_language(test: &str, name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("crabble-{}-{test}", std::process::id()))
+            .join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, contents) in files {
+            fs::write(dir.join(file), contents).unwrap();
+        }
+        dir
+    }
+
+    const LETTERS: &str = "letter,amount,score\n ,1,0\nA,3,1\nB,2,3\nQ,1,10\n";
+
+    #[test]
+    fn from_dir_registers_language() {
+        let dir = write_language(
+            "from_dir",
+            "house",
+            &[
+                ("letters.csv", LETTERS),
+                ("words.txt", "ab\nBA\nbad\nqua\n"),
+                (
+                    "metadata.txt",
+                    "# our club\ndisplay_name = House\nrack_size = 5\n",
+                ),
+            ],
+        );
+
+        let language = Language::from_dir(&dir).unwrap();
+        assert_eq!(language.name, "house");
+        assert_eq!(language.display_name, "House");
+        assert_eq!(language.rack_size, 5);
+        // "bad" and "qua" need letters that have no tile
+        assert!(language.contains("ab"));
+        assert!(language.contains("ba"));
+        assert_eq!(language.lexicons[0].len(), 2);
+
+        let found = Language::by_name("house").unwrap();
+        assert_eq!(found.rack_size, 5);
+        assert!(Lexicon::find("house", STANDARD_LEXICON)
+            .unwrap()
+            .contains("ba"));
+        assert!(available().contains(&LanguageEntry {
+            name: "house".into(),
+            display_name: "House".into(),
+            lexicons: vec![STANDARD_LEXICON.into()],
+        }));
+    }
+
+    #[test]
+    fn load_languages_skips_other_directories() {
+        let dir = write_language(
+            "load",
+            "quiz",
+            &[("letters.csv", LETTERS), ("words.txt", "")],
+        );
+        let parent = dir.parent().unwrap();
+        fs::create_dir_all(parent.join("notes")).unwrap();
+
+        assert_eq!(load_languages(parent).unwrap(), vec!["quiz".to_string()]);
+        assert_eq!(Language::by_name("quiz").unwrap().display_name, "quiz");
+    }
+
+    #[test]
+    fn lexicon_editions_can_be_combined() {
+        let dir = write_language(
+            "editions",
+            "club",
+            &[
+                ("letters.csv", LETTERS),
+                ("words.txt", "ab\n"),
+                ("words-tournament.txt", "ab\nbaa\n"),
+                ("words-family.txt", "aa\n"),
+            ],
+        );
+
+        let mut language = Language::from_dir(&dir).unwrap();
+        let names: Vec<_> = language.lexicons.iter().map(Lexicon::name).collect();
+        assert_eq!(names, vec![STANDARD_LEXICON, "family", "tournament"]);
+        assert!(!language.contains("baa"));
+
+        language.select_lexicons(&["tournament"]).unwrap();
+        assert!(language.contains("baa"));
+        assert!(!language.contains("aa"));
+
+        language.select_lexicons(&["family", "tournament"]).unwrap();
+        assert!(language.contains("baa"));
+        assert!(language.contains("aa"));
+        assert!(language.contains("ab"));
+
+        assert_eq!(
+            language.select_lexicons(&["collins"]).unwrap_err(),
+            CrabbleError::UnknownLexicon("collins".into())
+        );
+        assert_eq!(
+            language.select_lexicons(&[]).unwrap_err(),
+            CrabbleError::NoLexiconSelected
+        );
+        let selected: Vec<_> = language.selected_lexicons().map(Lexicon::name).collect();
+        assert_eq!(selected, vec!["family", "tournament"]);
+    }
+
+    #[test]
+    fn bad_metadata_is_refused() {
+        assert!(Metadata::parse("colour = blue").is_err());
+        assert!(Metadata::parse("rack_size = 0").is_err());
+        assert!(Metadata::parse("rack_size").is_err());
+
+        let dir = write_language(
+            "alphabet",
+            "abc",
+            &[
+                ("letters.csv", LETTERS),
+                ("words.txt", "ab\n"),
+                ("metadata.txt", "alphabet = AB"),
+            ],
+        );
+        assert_eq!(
+            Language::from_dir(&dir).unwrap_err(),
+            CrabbleError::LanguageFile("abc: tile q is not in the alphabet".into())
+        );
+        assert!(Language::from_dir(dir.join("missing")).is_err());
+    }
+
+    #[test]
+    fn words_are_split_into_tiles() {
+        let letters = |s: &str| -> Vec<Letter> {
+            s.split('-')
+                .map(|letter| Letter::new(letter).unwrap())
+                .collect()
+        };
+        let tiles = letters("a-c-ch-l-ll-m-o");
+
+        let mut found = spellings("llama", &tiles);
+        found.sort();
+        assert_eq!(found, vec![letters("l-l-a-m-a"), letters("ll-a-m-a")]);
+        assert_eq!(spellings("hola", &tiles), Vec::<Vec<Letter>>::new());
+
+        // only the longer tile fits at the start, but then the rest can't be spelled
+        let tiles = letters("ab-abc-cd");
+        assert!(can_spell("abcd", &tiles));
+        assert_eq!(spellings("abcd", &tiles), vec![letters("ab-cd")]);
+        assert!(!can_spell("abd", &tiles));
+
+        let dir = write_language(
+            "digraphs",
+            "digrafos",
+            &[
+                (
+                    "letters.csv",
+                    "letter,amount,score\n ,2,0\nA,9,1\nC,4,3\nCH,1,5\nL,4,1\nLL,1,8\nM,2,3\nO,8,1\n",
+                ),
+                ("words.txt", "llama\nmacho\nhola\ncal\n"),
+                ("metadata.txt", "alphabet = a c ch l ll m o"),
+            ],
+        );
+        let language = Language::from_dir(&dir).unwrap();
+        assert_eq!(language.lexicons[0].len(), 3);
+        assert!(language.contains("macho"));
+        assert!(!language.contains("hola"));
+        assert_eq!(language.spellings("cal"), vec![letters("c-a-l")]);
+    }
+
+    #[test]
+    fn letters_and_words_are_normalized() {
+        let dir = write_language(
+            "normalize",
+            "castellano",
+            &[
+                // the Ñ is written as an N with a combining tilde
+                (
+                    "letters.csv",
+                    "letter,amount,score\n ,2,0\nA,9,1\nC,4,3\nN,5,1\nN\u{303},1,8\nO,8,1\n",
+                ),
+                ("words.txt", "AÑO\nCAÑÓN\ncano\n"),
+                ("metadata.txt", "fold = Á:a Ó:o"),
+            ],
+        );
+        let language = Language::from_dir(&dir).unwrap();
+        let enye = Letter::new("ñ").unwrap();
+
+        assert_eq!(language.normalize("CAÑÓN"), "cañon");
+        assert!(language.contains("año"));
+        assert!(language.contains("cañon"));
+        assert!(language.contains("cano"));
+        assert_eq!(language.letter("Ñ"), Ok(enye));
+        assert_eq!(language.letter("n\u{303}"), Ok(enye));
+        assert_eq!(language.letter("ó"), Ok(Letter::from('o')));
+        assert_eq!(
+            language.letter("x"),
+            Err(CrabbleError::UnknownLetter("x".into()))
+        );
+
+        assert_eq!(language.values.get(HandTile::Letter(enye)), Ok(8));
+        assert_eq!(
+            language.values.get(HandTile::Letter('x'.into())),
+            Err(CrabbleError::UnknownLetter("x".into()))
+        );
+        assert!(Metadata::parse("fold = á").is_err());
+    }
+
+    #[test]
+    fn bundled_languages_are_available() {
+        let names: Vec<_> = available().into_iter().map(|entry| entry.name).collect();
+        assert!(names.contains(&"english".to_string()));
+        assert!(names.contains(&"dutch".to_string()));
+        assert_eq!(
+            Language::by_name("dutch").unwrap().display_name,
+            "Nederlands"
+        );
+
+        // the common editions only have words of the standard lexicons
+        for (name, common) in [
+            (
+                "english",
+                include_str!("../../data/english/words-common.txt"),
+            ),
+            ("dutch", include_str!("../../data/dutch/words-common.txt")),
+        ] {
+            let language = Language::by_name(name).unwrap();
+            let editions: Vec<_> = language.lexicons.iter().map(Lexicon::name).collect();
+            assert_eq!(editions, vec![STANDARD_LEXICON, COMMON_LEXICON]);
+            assert_eq!(language.lexicons[1].len(), common.lines().count());
+            for word in common.lines() {
+                assert!(language.lexicons[0].contains(word), "{word}");
+            }
+        }
+    }
+
+    #[test]
+    fn bundled_starter_word_lists() {
+        for (name, words) in [
+            ("german", include_str!("../../data/german/words.txt")),
+            ("french", include_str!("../../data/french/words.txt")),
+            ("spanish", include_str!("../../data/spanish/words.txt")),
+            ("italian", include_str!("../../data/italian/words.txt")),
+        ] {
+            let language = Language::by_name(name).unwrap();
+            assert!(!language.has_complete_lexicon());
+            for word in words.lines() {
+                assert!(
+                    language.contains(&language.normalize(word)),
+                    "{name}: {word}"
+                );
+            }
+        }
+
+        // accents are folded the way the metadata says, the Ñ and the digraphs are kept
+        let spanish = Language::by_name("spanish").unwrap();
+        assert_eq!(spanish.display_name, "Español");
+        assert!(spanish.contains("cancion"));
+        assert!(spanish.contains("año"));
+        assert_eq!(spanish.spellings("llama").len(), 2);
+        assert!(Language::by_name("german").unwrap().contains("strasse"));
+        assert!(Language::by_name("french").unwrap().contains("ecole"));
+        assert!(Language::by_name("italian").unwrap().contains("citta"));
+    }
+
+    #[test]
+    fn bundled_lexicon_matches_word_list() {
+        let built = Lexicon::find("english", STANDARD_LEXICON).unwrap();
+        let parsed = Lexicon::parse(
+            "english",
+            STANDARD_LEXICON,
+            include_str!("../../data/english/words.txt"),
+        );
+
+        assert_eq!(built.len(), parsed.len());
+        for word in ["cat", "zymurgy", "aa"] {
+            assert!(built.contains(word));
+        }
+        assert!(!built.contains("zzqx"));
+    }
+}
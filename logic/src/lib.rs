@@ -2,20 +2,46 @@
 
 pub mod asn;
 mod bag;
+pub mod dictionary;
+pub mod endgame;
 pub mod game;
+pub mod history;
 pub mod language;
+pub mod movegen;
+mod normalize;
+pub mod rules;
+#[cfg(feature = "serde")]
+pub mod save;
+pub mod score;
+pub mod simulation;
+pub mod strategy;
+#[cfg(test)]
+mod test_utils;
 
 use thiserror::Error;
 
 use std::fmt::Display;
 use std::ops::{Add, AddAssign, Sub};
-use std::sync::LazyLock;
+use std::path::Path;
+use std::str::FromStr;
+
+use dictionary::Dictionary;
+use language::Language;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BoardLayout {
     squares: Vec<Vec<Square>>,
 }
 
+/// The bundled board layouts, in the format `BoardLayout::from_str` reads
+static LAYOUT_DATA: [(&str, &str); 4] = [
+    ("standard", include_str!("../../data/scrabble_layout.txt")),
+    ("super", include_str!("../../data/super_layout.txt")),
+    ("friends", include_str!("../../data/friends_layout.txt")),
+    ("small", include_str!("../../data/small_layout.txt")),
+];
+
 #[derive(Error, Debug, PartialEq)]
 pub enum CrabbleError {
     #[error("Tile is already occupied")]
@@ -34,8 +60,62 @@ pub enum CrabbleError {
     TileOutOufBounds,
     #[error("Bag is currently empty")]
     BagEmpty,
-    #[error("Language can be english or dutch")]
+    #[error("Unknown or malformed language")]
     InvalidLanguage,
+    #[error("Could not load language: {0}")]
+    LanguageFile(String),
+    #[error("{0} is not a letter of this language")]
+    UnknownLetter(String),
+    #[error("There is no lexicon called {0}")]
+    UnknownLexicon(String),
+    #[error("Choose at least one lexicon")]
+    NoLexiconSelected,
+    #[error("There is no board layout called {0}")]
+    UnknownLayout(String),
+    #[error("Invalid board layout: {0}")]
+    InvalidLayout(String),
+    #[error("There is no computer player called {0}")]
+    UnknownBot(String),
+    #[error("The current player is not a computer player")]
+    NotABot,
+    #[error("The endgame can only be solved once the bag is empty")]
+    BagNotEmpty,
+    #[error("The endgame can only be solved between two players")]
+    EndgameNeedsTwoPlayers,
+    #[error("Hints are turned off for this game")]
+    HintsDisabled,
+    #[error("You have used up all your hints")]
+    NoHintsLeft,
+    #[error("Hints can be off, unlimited or a number, not {0}")]
+    InvalidHintPolicy(String),
+    #[error("Tile is not in the current player's hand")]
+    TileNotInHand,
+    #[error("The game is already over")]
+    GameOver,
+    #[error("Invalid words played: {}", .0.join(", "))]
+    InvalidWords(Vec<String>),
+    #[error("Challenges are not allowed with these rules")]
+    ChallengesDisabled,
+    #[error("There is no play that can be challenged")]
+    NothingToChallenge,
+    #[error("Tiles have already been placed this turn")]
+    TilesAlreadyPlaced,
+    #[error("Not enough tiles left in the bag to exchange")]
+    BagTooSmallToExchange,
+    #[error("Choose at least one tile to exchange")]
+    ExchangeEmpty,
+    #[error("The last play went out, it can only be challenged or accepted")]
+    LastPlayWentOut,
+    #[error("There is no provisional tile on that square")]
+    NoProvisionalTile,
+    #[error("There is no turn to undo")]
+    NothingToUndo,
+    #[error("There is no turn to redo")]
+    NothingToRedo,
+    #[error("Saved game is invalid: {0}")]
+    InvalidSave(String),
+    #[error("Saved game has version {0}, which is not supported")]
+    UnsupportedSaveVersion(u32),
 }
 
 impl BoardLayout {
@@ -67,6 +147,117 @@ impl BoardLayout {
 
         Self { squares }
     }
+
+    /// The standard 15x15 board
+    pub fn standard() -> Self {
+        BoardLayout::from_fn((15, 15), standard_board_layout)
+    }
+
+    /// The names of the bundled layouts, the standard board first
+    pub fn available() -> Vec<&'static str> {
+        LAYOUT_DATA.iter().map(|(name, _)| *name).collect()
+    }
+
+    /// One of the bundled layouts
+    pub fn by_name(name: &str) -> Result<Self, CrabbleError> {
+        let Some((_, layout)) = LAYOUT_DATA.iter().find(|(bundled, _)| *bundled == name) else {
+            return Err(CrabbleError::UnknownLayout(name.into()));
+        };
+        layout.parse()
+    }
+
+    /// Reads a layout in the format of `from_str` from a file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CrabbleError> {
+        let path = path.as_ref();
+        std::fs::read_to_string(path)
+            .map_err(|e| CrabbleError::InvalidLayout(format!("{}: {e}", path.display())))?
+            .parse()
+    }
+
+    /// One of the bundled layouts, or else the layout in the file at `name`
+    pub fn find(name: &str) -> Result<Self, CrabbleError> {
+        match Self::by_name(name) {
+            Err(CrabbleError::UnknownLayout(_)) if Path::new(name).is_file() => {
+                Self::from_file(name)
+            }
+            layout => layout,
+        }
+    }
+}
+
+/// Reads the format `Display` writes, one line per row of squares: `.` for an empty square, `*`
+/// for the center square, `+` for a center square that doesn't double the word, a digit for a
+/// letter multiplier and the multiplier added to a space for a word multiplier, so `"` is a double
+/// and `#` a triple word square.
+impl FromStr for BoardLayout {
+    type Err = CrabbleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut squares: Vec<Vec<Square>> = Vec::new();
+        let mut centers = 0;
+
+        for (line_number, line) in s.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut row = Vec::new();
+            for (column, c) in line.chars().enumerate() {
+                row.push(match c {
+                    '.' => Square::Empty,
+                    '*' => {
+                        centers += 1;
+                        Square::CenterSquare
+                    }
+                    '+' => {
+                        centers += 1;
+                        Square::PlainCenterSquare
+                    }
+                    '2'..='9' => Square::LetterMultiplier(c as i8 - b'0' as i8),
+                    '"'..=')' => Square::WordMultiplier(c as i8 - b' ' as i8),
+                    c => {
+                        return Err(CrabbleError::InvalidLayout(format!(
+                            "unknown square {c:?} on line {}, column {}",
+                            line_number + 1,
+                            column + 1
+                        )))
+                    }
+                });
+            }
+
+            if let Some(first) = squares.first() {
+                if row.len() != first.len() {
+                    return Err(CrabbleError::InvalidLayout(format!(
+                        "line {} has {} squares instead of {}",
+                        line_number + 1,
+                        row.len(),
+                        first.len()
+                    )));
+                }
+            }
+            squares.push(row);
+        }
+
+        // every square needs a coordinate that can be written in ASN
+        let max = asn::COORDINATE_RADIX as usize;
+        let (width, height) = (squares.len(), squares.first().map_or(0, Vec::len));
+        if width == 0 {
+            return Err(CrabbleError::InvalidLayout("there are no squares".into()));
+        }
+        if width > max || height > max {
+            return Err(CrabbleError::InvalidLayout(format!(
+                "the board is {width}x{height}, it can be at most {max}x{max}"
+            )));
+        }
+        if centers != 1 {
+            return Err(CrabbleError::InvalidLayout(format!(
+                "there should be one center square, not {centers}"
+            )));
+        }
+
+        Ok(BoardLayout { squares })
+    }
 }
 
 pub fn standard_board_layout(Coordinate { mut x, mut y }: Coordinate) -> Square {
@@ -116,6 +307,7 @@ impl Display for BoardLayout {
                     char::from_u32(match s {
                         Square::Empty => b'.',
                         Square::CenterSquare => b'*',
+                        Square::PlainCenterSquare => b'+',
                         Square::LetterMultiplier(x) => x as u8 + b'0',
                         Square::WordMultiplier(x) => x as u8 + b' ',
                     } as u32)
@@ -128,9 +320,17 @@ impl Display for BoardLayout {
     }
 }
 
+/// Every square is as wide as the widest tile on the board, so the columns stay lined up when
+/// there are tiles with more than one letter
 impl Display for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let (x_max, y_max) = self.layout.dimensions();
+        let width = self
+            .tiles_with_coordinates()
+            .filter_map(|(_, tile)| tile)
+            .map(|tile| tile.tile.tile.chars().count())
+            .max()
+            .unwrap_or(1);
         for y in 0..y_max {
             for x in 0..x_max {
                 let s = self
@@ -149,7 +349,7 @@ impl Display for Board {
                         if tile.is_provisional {
                             write!(f, "\x1b[35m").unwrap();
                         }
-                        write!(f, "{}", tile.tile.tile).unwrap();
+                        write!(f, "{:width$}", tile.tile.tile.as_str()).unwrap();
                         if tile.is_provisional {
                             write!(f, "\x1b[0m").unwrap();
                         }
@@ -157,10 +357,11 @@ impl Display for Board {
                     None => {
                         write!(
                             f,
-                            "{}",
+                            "{:width$}",
                             char::from_u32(match s {
                                 Square::Empty => b'.',
                                 Square::CenterSquare => b'*',
+                                Square::PlainCenterSquare => b'+',
                                 Square::LetterMultiplier(x) => x as u8 + b'0',
                                 Square::WordMultiplier(x) => x as u8 + b' ',
                             } as u32)
@@ -175,7 +376,8 @@ impl Display for Board {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Board {
     // the actual squares on the board
     layout: BoardLayout,
@@ -195,9 +397,16 @@ impl From<BoardLayout> for Board {
     }
 }
 
+impl Default for Board {
+    fn default() -> Self {
+        Board::new()
+    }
+}
+
 impl Board {
+    /// An empty standard board
     pub fn new() -> Board {
-        todo!()
+        BoardLayout::standard().into()
     }
 
     fn get_square(&self, coord: Coordinate) -> Option<Square> {
@@ -239,52 +448,47 @@ impl Board {
         coord: Coordinate,
         dir: Direction,
     ) -> impl Iterator<Item = Coordinate> {
-        let mut range_begin = coord.clone();
-        let mut range_end = coord.clone();
+        let mut range_begin = coord;
+        let mut range_end = coord;
 
         let offset = dir.to_offset();
 
         // if we're not at the end of the board, and if we haven't found an empty tile:
-        loop {
-            match self.get_tile(range_end + offset) {
-                Some(_) => range_end = range_end + offset,
-                None => break,
-            }
+        while self.get_tile(range_end + offset).is_some() {
+            range_end += offset;
         }
 
         // iterate the other way...
-        loop {
-            match self.get_tile(range_begin - offset) {
-                Some(_) => range_begin = range_begin - offset,
-                None => break,
-            }
+        while self.get_tile(range_begin - offset).is_some() {
+            range_begin = range_begin - offset;
         }
 
         let mut current_coord = range_begin;
         std::iter::from_fn(move || match dir {
             Direction::Horizontal => {
                 if current_coord.x > range_end.x {
-                    return None;
+                    None
                 } else {
                     let res = Some(current_coord);
                     current_coord += offset;
-                    return res;
+                    res
                 }
             }
             Direction::Vertical => {
                 if current_coord.y > range_end.y {
-                    return None;
+                    None
                 } else {
                     let res = Some(current_coord);
                     current_coord += offset;
-                    return res;
+                    res
                 }
             }
         })
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Coordinate {
     x: isize,
     y: isize,
@@ -319,6 +523,18 @@ impl Sub for Coordinate {
 }
 
 impl Coordinate {
+    pub fn new(x: isize, y: isize) -> Self {
+        Coordinate { x, y }
+    }
+
+    pub fn x(&self) -> isize {
+        self.x
+    }
+
+    pub fn y(&self) -> isize {
+        self.y
+    }
+
     fn checked_sub(self, other: Self) -> Option<Self> {
         if other.x > self.x || other.y > self.y {
             None
@@ -332,35 +548,182 @@ impl Coordinate {
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BoardTile {
     tile: Tile,
     is_provisional: bool,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tile {
-    tile: char,
+    tile: Letter,
     is_joker: bool,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+impl Tile {
+    pub fn new(tile: impl Into<Letter>, is_joker: bool) -> Self {
+        Tile {
+            tile: tile.into(),
+            is_joker,
+        }
+    }
+
+    /// The letter this tile stands for, which for a joker is the letter it was played as
+    pub fn letter(&self) -> Letter {
+        self.tile
+    }
+
+    pub fn is_joker(&self) -> bool {
+        self.is_joker
+    }
+}
+
+impl From<Tile> for HandTile {
+    fn from(tile: Tile) -> Self {
+        match tile.is_joker {
+            true => HandTile::Joker,
+            false => HandTile::Letter(tile.tile),
+        }
+    }
+}
+
+/// What is printed on a tile. Usually a single letter, but some languages have tiles with a few
+/// letters on them, like the Spanish CH, LL and RR. Words are spelled by putting the letters of
+/// their tiles after each other.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Letter {
+    /// The letters in UTF-8, padded with zeroes
+    bytes: [u8; Letter::CAPACITY],
+    len: u8,
+}
+
+impl Letter {
+    /// The most bytes of UTF-8 the letters of a tile can take up
+    pub const CAPACITY: usize = 12;
+
+    /// The tile with the letters of `symbol`, if it isn't empty or too long
+    pub fn new(symbol: &str) -> Option<Self> {
+        if symbol.is_empty() || symbol.len() > Self::CAPACITY {
+            return None;
+        }
+
+        let mut bytes = [0; Self::CAPACITY];
+        bytes[..symbol.len()].copy_from_slice(symbol.as_bytes());
+        Some(Letter {
+            bytes,
+            len: symbol.len() as u8,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        // only ever filled from a `str`
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap()
+    }
+
+    pub fn chars(&self) -> std::str::Chars<'_> {
+        self.as_str().chars()
+    }
+
+    /// Whether there is more than one letter on the tile
+    pub fn is_multi_char(&self) -> bool {
+        self.chars().nth(1).is_some()
+    }
+}
+
+impl AsRef<str> for Letter {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<char> for Letter {
+    fn from(letter: char) -> Self {
+        Letter::new(letter.encode_utf8(&mut [0; 4])).unwrap()
+    }
+}
+
+impl PartialEq<char> for Letter {
+    fn eq(&self, other: &char) -> bool {
+        let mut chars = self.chars();
+        chars.next() == Some(*other) && chars.next().is_none()
+    }
+}
+
+impl Display for Letter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::fmt::Debug for Letter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Letter {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Letter {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Letter::new(&s).ok_or_else(|| {
+            serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(&s),
+                &"the letters of a tile",
+            )
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum HandTile {
     Joker,
-    Letter(char),
+    Letter(Letter),
 }
 
 impl Display for HandTile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let ch = match self {
-            Self::Joker => '*',
-            Self::Letter(l) => *l,
-        };
+        match self {
+            Self::Joker => write!(f, "*"),
+            Self::Letter(letter) => write!(f, "{letter}"),
+        }
+    }
+}
 
-        write!(f, "{ch}")
+/// Hand tiles are saved the way they are displayed, `*` for a joker and the letters otherwise,
+/// which also lets them be used as keys in JSON objects.
+#[cfg(feature = "serde")]
+impl serde::Serialize for HandTile {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HandTile {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        match s.as_str() {
+            "*" => Ok(HandTile::Joker),
+            s => Letter::new(s).map(HandTile::Letter).ok_or_else(|| {
+                serde::de::Error::invalid_value(
+                    serde::de::Unexpected::Str(s),
+                    &"the letters of a tile or `*`",
+                )
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Hand {
     letters: Vec<HandTile>,
 }
@@ -388,17 +751,54 @@ impl Hand {
     pub fn is_empty(&self) -> bool {
         self.letters.is_empty()
     }
+
+    pub fn tiles(&self) -> &[HandTile] {
+        &self.letters
+    }
+
+    /// Returns how many copies of `tile` are in this hand.
+    pub fn count(&self, tile: HandTile) -> usize {
+        self.letters.iter().filter(|t| **t == tile).count()
+    }
+
+    /// Removes a single copy of `tile` from this hand.
+    fn remove(&mut self, tile: HandTile) -> Result<(), CrabbleError> {
+        let idx = self
+            .letters
+            .iter()
+            .position(|t| *t == tile)
+            .ok_or(CrabbleError::TileNotInHand)?;
+        self.letters.remove(idx);
+        Ok(())
+    }
+}
+
+impl From<Vec<HandTile>> for Hand {
+    fn from(letters: Vec<HandTile>) -> Self {
+        Hand { letters }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Square {
     Empty,
     CenterSquare,
+    /// Where the first word goes, like the `CenterSquare`, but without doubling it
+    PlainCenterSquare,
     LetterMultiplier(i8),
     WordMultiplier(i8),
 }
 
-#[derive(Copy, Clone, Debug)]
+impl Square {
+    /// Whether the first word has to be played through this square
+    pub fn is_center(self) -> bool {
+        matches!(self, Square::CenterSquare | Square::PlainCenterSquare)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     Horizontal,
     Vertical,
@@ -420,42 +820,59 @@ impl Direction {
     }
 }
 
-/// Implements whether a certain play is valid. Returns whether the play is valid.
-fn challenge(
-    board: &Board,
-    word: impl Iterator<Item = Coordinate> + Clone,
-    dir: Direction,
-) -> bool {
-    if !check_if_valid(word.clone().map(|coord| board.get_tile(coord).unwrap())) {
-        return false;
-    }
+/// Returns the words formed by the tiles placed at `placed`: the word along `dir` first,
+/// followed by every word of at least two letters they form in the other direction.
+fn formed_words(board: &Board, placed: &[Coordinate], dir: Direction) -> Vec<Vec<Coordinate>> {
+    let Some(first) = placed.first() else {
+        return vec![];
+    };
+
+    let mut words = vec![board.find_range(*first, dir).collect::<Vec<_>>()];
     let other_dir = dir.flip();
-    for letter in word {
-        let word = board
-            .find_range(letter, other_dir)
-            .map(|coord| board.get_tile(coord).unwrap());
-        if !check_if_valid(word) {
-            return false;
+    for coord in placed {
+        let word: Vec<_> = board.find_range(*coord, other_dir).collect();
+        if word.len() > 1 {
+            words.push(word);
         }
     }
 
-    true
+    words
 }
 
-fn check_if_valid(word: impl Iterator<Item = BoardTile>) -> bool {
-    static WORDS: LazyLock<Vec<&str>> = LazyLock::new(|| {
-        include_str!("../../data/english/words.txt")
-            .lines()
-            .collect()
-    });
+fn word_string(board: &Board, word: &[Coordinate]) -> String {
+    word.iter()
+        .map(|coord| board.get_tile(*coord).unwrap().tile.tile.to_string())
+        .collect()
+}
 
-    let word: String = word.map(|w| w.tile.tile).collect();
+/// Implements whether a certain play is valid. Returns the words formed by the play that are
+/// not in `lexicon`, so an empty result means the play is valid.
+fn challenge(
+    board: &Board,
+    placed: &[Coordinate],
+    dir: Direction,
+    language: &Language,
+) -> Vec<String> {
+    formed_words(board, placed, dir)
+        .iter()
+        .filter(|word| {
+            !check_if_valid(
+                word.iter().map(|coord| board.get_tile(*coord).unwrap()),
+                language,
+            )
+        })
+        .map(|word| word_string(board, word))
+        .collect()
+}
+
+fn check_if_valid(word: impl Iterator<Item = BoardTile>, language: &Language) -> bool {
+    let word = word.map(|w| w.tile.tile.to_string()).collect::<String>();
 
     if word.is_empty() {
         return false;
     }
 
-    WORDS.binary_search(&word.as_ref()).is_ok()
+    language.contains(&word)
 }
 
 #[cfg(test)]
@@ -465,6 +882,8 @@ mod test {
     use asn::ASN;
     use game::*;
     use language::Language;
+    use rules::{ChallengeOutcome, ChallengeRule, Rules, Validation};
+    use test_utils::*;
 
     use super::*;
 
@@ -473,46 +892,268 @@ mod test {
         let layout = BoardLayout::from_fn((15, 15), standard_board_layout);
         let s = layout.to_string();
         assert_eq!(s, include_str!("../../data/scrabble_layout.txt"),);
-    }
 
-    fn make_game() -> Game {
-        let layout = BoardLayout::from_fn((15, 15), standard_board_layout);
+        // the default board is an empty one with this layout
+        let board = Board::default();
+        assert_eq!(board, Board::from(layout));
+        assert_eq!(board.provisionary_tiles_count, 0);
+    }
 
-        let players = vec![
-            Player::new("Gamer 1".to_string()),
-            Player::new("Player 2".to_string()),
-        ];
+    #[test]
+    fn layouts_are_parsed() {
+        assert_eq!(
+            BoardLayout::by_name("standard"),
+            Ok(BoardLayout::standard())
+        );
+        for name in BoardLayout::available() {
+            let layout = BoardLayout::by_name(name).unwrap();
+            assert_eq!(layout.to_string().parse(), Ok(layout.clone()));
+        }
+        let small = BoardLayout::find("small").unwrap();
+        assert_eq!(small.dimensions(), (11, 11));
+        assert_eq!(small.get(Coordinate::new(5, 5)), Some(Square::CenterSquare));
+        let friends = BoardLayout::by_name("friends").unwrap();
+        assert_eq!(
+            friends.get(Coordinate::new(7, 7)),
+            Some(Square::PlainCenterSquare)
+        );
+        assert_eq!(
+            BoardLayout::find("../data/super_layout.txt").unwrap(),
+            BoardLayout::by_name("super").unwrap()
+        );
+
+        let invalid = |layout: &str| match layout.parse::<BoardLayout>() {
+            Err(CrabbleError::InvalidLayout(e)) => e,
+            layout => panic!("{layout:?}"),
+        };
+        assert_eq!(
+            invalid("..\n.*x\n"),
+            "unknown square 'x' on line 2, column 3"
+        );
+        assert_eq!(invalid("...\n.*\n"), "line 2 has 2 squares instead of 3");
+        assert_eq!(
+            invalid("#.\n.#\n"),
+            "there should be one center square, not 0"
+        );
+        assert_eq!(invalid("\n"), "there are no squares");
+        assert_eq!(
+            BoardLayout::find("huge"),
+            Err(CrabbleError::UnknownLayout("huge".into()))
+        );
+    }
 
-        Game::new(players, layout, Language::by_name("english").unwrap())
+    #[test]
+    fn games_on_other_boards() {
+        let layout = BoardLayout::by_name("super").unwrap();
+        let mut game = Game::new(players(), layout, Language::by_name("english").unwrap());
+
+        // the center of a 21x21 board is past the base 15 digits
+        run_rigged("aahcat", &mut game).unwrap();
+        assert_eq!(game.history()[0].score, 10);
+
+        let line = &ASN::from_str("kkvdog").unwrap().lines[0];
+        assert_eq!(line.coord, Coordinate::new(20, 20));
+        assert_eq!(line.to_string(), "kkvdog");
+        assert!(game.board().get_square(Coordinate::new(20, 20)).is_some());
+        assert!(Board::new().get_square(Coordinate::new(20, 20)).is_none());
+
+        // the center of the friends board doesn't double the first word
+        let layout = BoardLayout::by_name("friends").unwrap();
+        let mut game = Game::new(players(), layout, Language::by_name("english").unwrap());
+        run_rigged("77hcat", &mut game).unwrap();
+        assert_eq!(game.history()[0].score, 5);
     }
 
     #[test]
     fn asn_test_word_extension() {
         let mut game = make_game();
-        let a = ASN::from_str("77hcat\na7hs").unwrap();
-        a.run(&mut game, true).unwrap();
+        run_rigged("77hcat\na7hs", &mut game).unwrap();
     }
 
     #[test]
     fn asn_invalid_play() {
         let mut game = make_game();
-        let a = ASN::from_str("77hcat\ne8hs").unwrap();
-        let err = a.run(&mut game, false).unwrap_err();
+        let err = run_rigged("77hcat\ne8hs", &mut game).unwrap_err();
         assert_eq!(err, CrabbleError::WordNotAdjacent);
+
+        // the failed play is taken back off the board
+        assert_eq!(game.get_tile(Coordinate { x: 14, y: 8 }), None);
+        assert_eq!(game.board().provisionary_tiles_count, 0);
     }
 
     #[test]
     fn asn_invalid_play_overlap() {
         let mut game = make_game();
-        let a = ASN::from_str("77hcat\n97hmeow").unwrap();
-        let err = a.run(&mut game, false).unwrap_err();
+        let err = run_rigged("77hcat\n97hmeow", &mut game).unwrap_err();
         assert_eq!(err, CrabbleError::TileOccupied);
     }
 
     #[test]
     fn asn_catgirl_extension() {
+        // "catgirls" is not in the dictionary
+        let mut game = make_game_with_rules(Rules {
+            validation: Validation::Off,
+            ..Rules::default()
+        });
+        run_rigged("77hgirl\n47hcats", &mut game).unwrap();
+    }
+
+    #[test]
+    fn asn_invalid_word() {
+        let mut game = make_game();
+        let err = run_rigged("77hcat\n88vzqx", &mut game).unwrap_err();
+        assert_eq!(err, CrabbleError::InvalidWords(vec!["azqx".to_string()]));
+    }
+
+    #[test]
+    fn asn_invalid_cross_word() {
+        let mut game = make_game();
+        // "at" is fine, but it turns "cat" into "catt"
+        let err = run_rigged("77hcat\na6vat", &mut game).unwrap_err();
+        assert_eq!(err, CrabbleError::InvalidWords(vec!["catt".to_string()]));
+    }
+
+    #[test]
+    fn asn_uses_lexicon_of_language() {
+        let dutch = |validation| {
+            let rules = Rules {
+                validation,
+                ..Rules::default()
+            };
+            let language = Language::by_name("dutch").unwrap();
+            Game::with_rules(players(), BoardLayout::standard(), language, rules)
+        };
+
+        // challenges are decided by the Dutch word list
+        let mut game = dutch(Validation::Challenge(ChallengeRule::Single));
+        run_rigged("77hcat", &mut game).unwrap();
+        assert_eq!(
+            game.challenge(),
+            Ok(ChallengeOutcome::Withdrawn(vec!["cat".to_string()]))
+        );
+        run_rigged("77hfiets", &mut game).unwrap();
+        assert_eq!(game.challenge(), Ok(ChallengeOutcome::Upheld));
+
+        // the IJ tile spells the same words as an I and a J
+        let mut game = dutch(Validation::Challenge(ChallengeRule::Single));
+        run_rigged("77h(ij)s", &mut game).unwrap();
+        assert_eq!(game.challenge(), Ok(ChallengeOutcome::Upheld));
+        assert_eq!(game.history()[0].score, 12);
+
+        // plays are checked against the same list without challenges, even though it is short
+        assert!(!Language::by_name("dutch").unwrap().has_complete_lexicon());
+        let mut game = dutch(Validation::Auto);
+        assert_eq!(
+            run_rigged("77hcat", &mut game).unwrap_err(),
+            CrabbleError::InvalidWords(vec!["cat".to_string()])
+        );
+        run_rigged("77hfiets", &mut game).unwrap();
+        assert_eq!(
+            run_rigged("77hfiets", &mut make_game()).unwrap_err(),
+            CrabbleError::InvalidWords(vec!["fiets".to_string()])
+        );
+    }
+
+    #[test]
+    fn asn_in_bundled_languages() {
+        let game = |name, validation| {
+            let rules = Rules {
+                validation,
+                ..Rules::default()
+            };
+            let language = Language::by_name(name).unwrap();
+            Game::with_rules(players(), BoardLayout::standard(), language, rules)
+        };
+
+        for (name, asn) in [
+            ("german", "77hbär"),
+            ("french", "77hchat"),
+            ("spanish", "77haño"),
+            ("spanish", "77h(ll)ama"),
+            ("italian", "77hgatto"),
+        ] {
+            run_rigged(asn, &mut game(name, Validation::Auto)).unwrap();
+            assert_eq!(
+                run_rigged("77hcat", &mut game(name, Validation::Auto)).unwrap_err(),
+                CrabbleError::InvalidWords(vec!["cat".to_string()])
+            );
+
+            let mut challenged = game(name, Validation::Challenge(ChallengeRule::Single));
+            run_rigged(asn, &mut challenged).unwrap();
+            assert_eq!(
+                challenged.challenge(),
+                Ok(ChallengeOutcome::Upheld),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn asn_multi_letter_tiles() {
+        let language = Language::parse_csv(
+            "digraphs",
+            "letter,amount,score\n ,2,0\nA,10,1\nCH,1,5\nL,4,1\nLL,1,8\nM,2,3\nO,8,1\n",
+        )
+        .unwrap()
+        .with_lexicons(vec![language::Lexicon::new(
+            "digraphs",
+            language::STANDARD_LEXICON,
+            ["llama", "macho"],
+        )]);
+        let layout = BoardLayout::from_fn((15, 15), standard_board_layout);
+        let players = vec![Player::new("Jugador 1".to_string())];
+        let mut game = Game::new(players, layout, language);
+
+        run_rigged("77h(ll)ama\n98va(ch)o", &mut game).unwrap();
+        let line = game.history()[1].action.clone();
+        let history::TurnAction::Play { tiles, words, .. } = line else {
+            panic!("{line:?}");
+        };
+        assert_eq!(tiles[1].1, Tile::new(Letter::new("ch").unwrap(), false));
+        assert_eq!(words, vec!["macho".to_string()]);
+
+        // every square is two wide now, so the columns still line up
+        let board = game.board().to_string();
+        assert!(board.lines().all(|line| line.chars().count() == 30));
+        assert!(board.contains("lla m a "));
+
+        assert!(matches!(
+            ASN::from_str("77h(ll"),
+            Err(asn::ASNError::InvalidTileCharacter)
+        ));
+        assert!(ASN::from_str("77h()").is_err());
+    }
+
+    #[test]
+    fn asn_letters_in_any_case() {
+        let mut game = make_game();
+        run_rigged("77hcat", &mut game).unwrap();
+
+        game.players[1].hand = Hand::from(vec![HandTile::Letter('s'.into())]);
+        ASN::from_str("a7hS")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+        assert_eq!(game.history()[1].score, 6);
+
+        game.players[0].hand = Hand::from(vec![HandTile::Joker]);
+        assert_eq!(
+            ASN::from_str("78v*é").unwrap().run(&mut game, false),
+            Err(CrabbleError::UnknownLetter("é".into()))
+        );
+    }
+
+    #[test]
+    fn asn_tile_not_in_hand() {
         let mut game = make_game();
-        let a = ASN::from_str("77hgirl\n47hcats").unwrap();
-        a.run(&mut game, true).unwrap();
+        game.players[0].hand = Hand::from(vec![
+            HandTile::Letter('c'.into()),
+            HandTile::Letter('a'.into()),
+        ]);
+        let err = ASN::from_str("77hcat")
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap_err();
+        assert_eq!(err, CrabbleError::TileNotInHand);
     }
 }
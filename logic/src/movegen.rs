@@ -0,0 +1,560 @@
+use std::{cmp::Reverse, collections::HashSet};
+
+use crate::{
+    dictionary::Dictionary,
+    formed_words,
+    game::{placed_word, score_word},
+    language::Language,
+    rules::Rules,
+    score::ScoreBreakdown,
+    word_string, Board, BoardTile, Coordinate, Direction, Hand, HandTile, Letter, Square, Tile,
+};
+
+/// A legal play, as found by `generate`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    /// The tiles to place, with jokers set to the letter they stand for
+    pub tiles: Vec<(Coordinate, Tile)>,
+    /// The direction of the main word, as `end_turn` would pick it
+    pub dir: Direction,
+    /// The main word first, followed by the words formed in the other direction
+    pub words: Vec<String>,
+    pub breakdown: ScoreBreakdown,
+}
+
+impl Move {
+    pub fn score(&self) -> isize {
+        self.breakdown.total()
+    }
+
+    /// The tiles of `rack` that are left after this move
+    pub fn leave(&self, rack: &[HandTile]) -> Vec<HandTile> {
+        let mut leave = rack.to_vec();
+        for (_, tile) in &self.tiles {
+            if let Some(idx) = leave.iter().position(|t| *t == HandTile::from(*tile)) {
+                leave.remove(idx);
+            }
+        }
+        leave
+    }
+}
+
+/// A move suggested by `Game::hints`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hint {
+    /// The move written in ASN, ready to be submitted
+    pub asn: String,
+    pub score: isize,
+    /// The main word first, followed by the words formed in the other direction
+    pub words: Vec<String>,
+    /// The tiles left on the rack after the move, before drawing new ones
+    pub leave: Vec<HandTile>,
+    pub play: Move,
+}
+
+/// Lists every legal play of the tiles in `hand` on `board`, highest score first.
+///
+/// Words are looked up in the selected lexicons of `language`, and the plays are checked and
+/// scored by the same code as `Game::end_turn`. Provisional tiles on `board` are ignored.
+///
+/// The search follows Appel and Jacobson: words are only built through anchors (the empty
+/// squares next to a tile), and letters are only tried where they also form valid words across.
+pub fn generate(board: &Board, hand: &Hand, language: &Language, rules: &Rules) -> Vec<Move> {
+    let mut board = board.clone();
+    for (coord, tile) in board.tiles_with_coordinates().collect::<Vec<_>>() {
+        if tile.is_some_and(|tile| tile.is_provisional) {
+            *board.get_tile_mut(coord).unwrap() = None;
+        }
+    }
+    board.provisionary_tiles_count = 0;
+
+    let mut generator = Generator {
+        board: &board,
+        language,
+        alphabet: language.letters().collect(),
+        rack: Rack::new(hand),
+        found: HashSet::new(),
+        plays: Vec::new(),
+    };
+    for dir in [Direction::Horizontal, Direction::Vertical] {
+        generator.generate(dir);
+    }
+    let plays = generator.plays;
+
+    let mut moves: Vec<Move> = plays
+        .into_iter()
+        .filter_map(|tiles| evaluate(&mut board, language, rules, tiles))
+        .collect();
+    moves.sort_by_key(|play| Reverse(play.score()));
+    moves
+}
+
+/// Puts `tiles` on the board to check and score them like `end_turn` does, and takes them off
+/// again
+fn evaluate(
+    board: &mut Board,
+    language: &Language,
+    rules: &Rules,
+    tiles: Vec<(Coordinate, Tile)>,
+) -> Option<Move> {
+    for (coord, tile) in &tiles {
+        *board.get_tile_mut(*coord).unwrap() = Some(BoardTile {
+            tile: *tile,
+            is_provisional: true,
+        });
+    }
+    board.provisionary_tiles_count = tiles.len();
+
+    let play = placed_word(board).ok().map(|(first, dir)| {
+        let breakdown = score_word(board, language, rules, board.find_range(first, dir), dir);
+        let placed: Vec<Coordinate> = tiles.iter().map(|(coord, _)| *coord).collect();
+        let words = formed_words(board, &placed, dir)
+            .iter()
+            .map(|word| word_string(board, word))
+            .collect();
+        Move {
+            tiles: tiles.clone(),
+            dir,
+            words,
+            breakdown,
+        }
+    });
+
+    for (coord, _) in &tiles {
+        *board.get_tile_mut(*coord).unwrap() = None;
+    }
+    board.provisionary_tiles_count = 0;
+
+    play
+}
+
+/// The tiles that are still available while a play is being built
+struct Rack {
+    letters: Vec<(Letter, usize)>,
+    jokers: usize,
+}
+
+impl Rack {
+    fn new(hand: &Hand) -> Self {
+        let mut rack = Rack {
+            letters: Vec::new(),
+            jokers: 0,
+        };
+        for tile in hand.tiles() {
+            rack.put(*tile);
+        }
+        rack
+    }
+
+    fn len(&self) -> usize {
+        self.jokers + self.letters.iter().map(|(_, count)| count).sum::<usize>()
+    }
+
+    fn count(&self, letter: Letter) -> usize {
+        self.letters
+            .iter()
+            .find(|(l, _)| *l == letter)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    fn take(&mut self, tile: HandTile) {
+        match tile {
+            HandTile::Joker => self.jokers -= 1,
+            HandTile::Letter(letter) => {
+                let (_, count) = self.letters.iter_mut().find(|(l, _)| *l == letter).unwrap();
+                *count -= 1;
+            }
+        }
+    }
+
+    fn put(&mut self, tile: HandTile) {
+        match tile {
+            HandTile::Joker => self.jokers += 1,
+            HandTile::Letter(letter) => match self.letters.iter_mut().find(|(l, _)| *l == letter) {
+                Some((_, count)) => *count += 1,
+                None => self.letters.push((letter, 1)),
+            },
+        }
+    }
+}
+
+/// The squares of one row or column, with what is known about them before the search
+struct Line {
+    coords: Vec<Coordinate>,
+    /// The letters that form valid words across, for the empty squares that have tiles next to
+    /// them across. `None` allows any letter.
+    cross_checks: Vec<Option<Vec<Letter>>>,
+    anchor: usize,
+}
+
+struct Generator<'a> {
+    board: &'a Board,
+    language: &'a Language,
+    /// The letters a joker can stand for
+    alphabet: Vec<Letter>,
+    rack: Rack,
+    found: HashSet<Vec<(Coordinate, Tile)>>,
+    plays: Vec<Vec<(Coordinate, Tile)>>,
+}
+
+impl Generator<'_> {
+    fn generate(&mut self, dir: Direction) {
+        let (width, height) = self.board.layout.dimensions();
+        let (lines, length) = match dir {
+            Direction::Horizontal => (height, width),
+            Direction::Vertical => (width, height),
+        };
+        let board_is_empty = self
+            .board
+            .tiles_with_coordinates()
+            .all(|(_, tile)| tile.is_none());
+
+        for line in 0..lines as isize {
+            let coords: Vec<Coordinate> = (0..length as isize)
+                .map(|pos| match dir {
+                    Direction::Horizontal => Coordinate::new(pos, line),
+                    Direction::Vertical => Coordinate::new(line, pos),
+                })
+                .collect();
+            let anchors: Vec<bool> = coords
+                .iter()
+                .map(|coord| self.is_anchor(*coord, board_is_empty))
+                .collect();
+            let cross_checks = coords
+                .iter()
+                .map(|coord| self.cross_check(*coord, dir))
+                .collect();
+            let mut line = Line {
+                coords,
+                cross_checks,
+                anchor: 0,
+            };
+
+            for anchor in (0..length).filter(|pos| anchors[*pos]) {
+                line.anchor = anchor;
+
+                if anchor > 0 && self.letter_at(line.coords[anchor - 1]).is_some() {
+                    // the tiles before the anchor are the start of the word
+                    let mut start = anchor;
+                    while start > 0 && self.letter_at(line.coords[start - 1]).is_some() {
+                        start -= 1;
+                    }
+                    let prefix: String = line.coords[start..anchor]
+                        .iter()
+                        .filter_map(|coord| self.letter_at(*coord))
+                        .map(|letter| letter.to_string())
+                        .collect();
+                    if let Some(node) = self.language.walk(&prefix) {
+                        self.extend_right(&line, &mut Vec::new(), node, anchor);
+                    }
+                } else {
+                    // new tiles can go before the anchor, up to the previous anchor
+                    let mut limit = 0;
+                    while limit < anchor && !anchors[anchor - limit - 1] {
+                        limit += 1;
+                    }
+                    let limit = limit.min(self.rack.len().saturating_sub(1));
+                    self.left_part(&line, &mut Vec::new(), self.language.root(), limit);
+                }
+            }
+        }
+    }
+
+    fn letter_at(&self, coord: Coordinate) -> Option<Letter> {
+        self.board.get_tile(coord).map(|tile| tile.tile.tile)
+    }
+
+    /// Plays have to cover an anchor: an empty square next to a tile, or the center square on an
+    /// empty board
+    fn is_anchor(&self, coord: Coordinate, board_is_empty: bool) -> bool {
+        if self.letter_at(coord).is_some() {
+            return false;
+        }
+        if board_is_empty {
+            return self.board.get_square(coord).is_some_and(Square::is_center);
+        }
+
+        [
+            Coordinate::new(1, 0),
+            Coordinate::new(0, 1),
+            Coordinate::new(-1, 0),
+            Coordinate::new(0, -1),
+        ]
+        .iter()
+        .any(|offset| self.letter_at(coord + *offset).is_some())
+    }
+
+    fn cross_check(&self, coord: Coordinate, dir: Direction) -> Option<Vec<Letter>> {
+        if self.letter_at(coord).is_some() {
+            return None;
+        }
+
+        let offset = dir.flip().to_offset();
+        let mut before = Vec::new();
+        let mut pos = coord - offset;
+        while let Some(letter) = self.letter_at(pos) {
+            before.push(letter);
+            pos = pos - offset;
+        }
+        let mut after = String::new();
+        let mut pos = coord + offset;
+        while let Some(letter) = self.letter_at(pos) {
+            after.push_str(letter.as_str());
+            pos += offset;
+        }
+        if before.is_empty() && after.is_empty() {
+            return None;
+        }
+
+        let before: String = before.iter().rev().map(Letter::as_str).collect();
+        let allowed = self
+            .alphabet
+            .iter()
+            .filter(|letter| self.language.contains(&format!("{before}{letter}{after}")))
+            .copied()
+            .collect();
+        Some(allowed)
+    }
+
+    /// The tiles from the rack that can follow `node`, with the node they lead to
+    fn options(
+        &self,
+        node: &<Language as Dictionary>::Node,
+        allowed: Option<&[Letter]>,
+    ) -> Vec<(Tile, <Language as Dictionary>::Node)> {
+        let is_allowed = |letter: Letter| allowed.is_none_or(|allowed| allowed.contains(&letter));
+        let mut options = Vec::new();
+
+        if self.rack.jokers > 0 {
+            for (first, child) in self.language.children(node) {
+                // the tiles starting with this letter, followed by the rest of their letters
+                for letter in &self.alphabet {
+                    let Some(rest) = letter.as_str().strip_prefix(first) else {
+                        continue;
+                    };
+                    if !is_allowed(*letter) {
+                        continue;
+                    }
+                    let Some(child) = self.language.follow(&child, rest) else {
+                        continue;
+                    };
+                    if self.rack.count(*letter) > 0 {
+                        options.push((Tile::new(*letter, false), child.clone()));
+                    }
+                    options.push((Tile::new(*letter, true), child));
+                }
+            }
+        } else {
+            for (letter, count) in &self.rack.letters {
+                if *count == 0 || !is_allowed(*letter) {
+                    continue;
+                }
+                if let Some(child) = self.language.follow(node, letter.as_str()) {
+                    options.push((Tile::new(*letter, false), child));
+                }
+            }
+        }
+
+        options
+    }
+
+    /// Tries every start of a word that fits in the `limit` empty squares before the anchor
+    fn left_part(
+        &mut self,
+        line: &Line,
+        left: &mut Vec<Tile>,
+        node: <Language as Dictionary>::Node,
+        limit: usize,
+    ) {
+        let start = line.anchor - left.len();
+        let mut placed = left
+            .iter()
+            .enumerate()
+            .map(|(i, tile)| (start + i, *tile))
+            .collect();
+        self.extend_right(line, &mut placed, node.clone(), line.anchor);
+
+        if limit == 0 {
+            return;
+        }
+        for (tile, child) in self.options(&node, None) {
+            self.rack.take(tile.into());
+            left.push(tile);
+            self.left_part(line, left, child, limit - 1);
+            left.pop();
+            self.rack.put(tile.into());
+        }
+    }
+
+    /// Continues the word from the square at `pos`, placing tiles on the empty squares and
+    /// following the tiles already on the board
+    fn extend_right(
+        &mut self,
+        line: &Line,
+        placed: &mut Vec<(usize, Tile)>,
+        node: <Language as Dictionary>::Node,
+        pos: usize,
+    ) {
+        if let Some(letter) = line
+            .coords
+            .get(pos)
+            .and_then(|coord| self.letter_at(*coord))
+        {
+            if let Some(child) = self.language.follow(&node, letter.as_str()) {
+                self.extend_right(line, placed, child, pos + 1);
+            }
+            return;
+        }
+
+        if pos > line.anchor && self.language.is_word(&node) {
+            self.record(line, placed);
+        }
+        if pos >= line.coords.len() {
+            return;
+        }
+
+        for (tile, child) in self.options(&node, line.cross_checks[pos].as_deref()) {
+            self.rack.take(tile.into());
+            placed.push((pos, tile));
+            self.extend_right(line, placed, child, pos + 1);
+            placed.pop();
+            self.rack.put(tile.into());
+        }
+    }
+
+    fn record(&mut self, line: &Line, placed: &[(usize, Tile)]) {
+        let tiles: Vec<(Coordinate, Tile)> = placed
+            .iter()
+            .map(|(pos, tile)| (line.coords[*pos], *tile))
+            .collect();
+
+        // a single tile can be found in both directions
+        if self.found.insert(tiles.clone()) {
+            self.plays.push(tiles);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{asn::ASN, language::Lexicon, test_utils::*};
+
+    use super::*;
+
+    #[test]
+    fn opening_moves_cover_the_center() {
+        let mut game = make_game();
+        game.players[0].hand = hand("cat");
+        let moves = game.legal_moves().unwrap();
+
+        let center = Coordinate::new(7, 7);
+        assert!(!moves.is_empty());
+        assert!(moves
+            .iter()
+            .all(|play| play.tiles.iter().any(|(coord, _)| *coord == center)));
+        assert!(moves.iter().any(|play| play.words == ["cat"]));
+        assert!(moves.iter().any(|play| play.words == ["act"]));
+        // "at" and "ta" are words too, so not every move uses all three tiles
+        assert!(moves.iter().any(|play| play.tiles.len() == 2));
+        assert!(moves.windows(2).all(|w| w[0].score() >= w[1].score()));
+    }
+
+    #[test]
+    fn moves_are_accepted_by_end_turn() {
+        let mut game = make_game();
+        run_rigged("77hcat\n98voe", &mut game).unwrap();
+        game.players[0].hand = hand("rs*");
+
+        let moves = game.legal_moves().unwrap();
+        assert!(moves.len() > 100);
+        for play in &moves {
+            let mut after = game.clone();
+            let breakdown = after.play_move(play).unwrap();
+            assert_eq!(breakdown, play.breakdown, "{play:?}");
+            assert_eq!(after.history().last().unwrap().score, play.score());
+        }
+    }
+
+    #[test]
+    fn jokers_stand_for_any_letter() {
+        let mut game = make_game();
+        game.players[0].hand = hand("q*");
+        let moves = game.legal_moves().unwrap();
+
+        // "qi" needs the joker as the "i"
+        let qi = moves.iter().find(|play| play.words == ["qi"]).unwrap();
+        assert!(qi
+            .tiles
+            .iter()
+            .any(|(_, tile)| *tile == Tile::new('i', true)));
+        assert!(moves
+            .iter()
+            .all(|play| play.tiles.iter().any(|(_, tile)| tile.is_joker())));
+    }
+
+    #[test]
+    fn tiles_can_have_several_letters() {
+        let language = Language::parse_csv(
+            "digraphs",
+            "letter,amount,score\n ,2,0\nA,10,1\nL,4,1\nLL,1,8\nM,2,3\n",
+        )
+        .unwrap()
+        .with_lexicons(vec![Lexicon::new("digraphs", "standard", ["llama"])]);
+        let ll = Letter::new("ll").unwrap();
+        let mut game = make_game_in(language);
+
+        // "llama" can be spelled with the LL tile or with two L tiles
+        let mut tiles = hand("llama").tiles().to_vec();
+        tiles.push(HandTile::Letter(ll));
+        game.players[0].hand = Hand::from(tiles);
+        let moves = game.legal_moves().unwrap();
+        assert!(moves.iter().all(|play| play.words == ["llama"]));
+        let with_ll = moves.iter().find(|play| play.tiles.len() == 4).unwrap();
+        assert!(moves.iter().any(|play| play.tiles.len() == 5));
+        assert_eq!(with_ll.score(), (8 + 1 + 3 + 1) * 2);
+
+        // the hint is written so it can be submitted as it is
+        let hint = game.hints(10).unwrap();
+        let hint = hint.iter().find(|hint| hint.play == *with_ll).unwrap();
+        assert!(hint.asn.contains("(ll)ama"), "{}", hint.asn);
+        ASN::from_str(&hint.asn)
+            .unwrap()
+            .run(&mut game, false)
+            .unwrap();
+        assert_eq!(game.history()[0].score, with_ll.score());
+
+        // a joker can stand for a tile with several letters as well
+        let mut game = make_game_in(game.language().clone());
+        game.players[0].hand = hand("*ama");
+        let moves = game.legal_moves().unwrap();
+        assert!(moves.iter().any(|play| play
+            .tiles
+            .iter()
+            .any(|(_, tile)| *tile == Tile::new(ll, true))));
+    }
+
+    #[test]
+    fn moves_respect_selected_lexicons() {
+        let words = |play: &Move| play.words[0].clone();
+        let mut language = Language::by_name("english").unwrap().with_lexicons(vec![
+            Lexicon::new("english", "small", ["cat"]),
+            Lexicon::new("english", "other", ["act", "ta"]),
+        ]);
+
+        let mut game = make_game_in(language.clone());
+        game.players[0].hand = hand("cat");
+        let found: HashSet<_> = game.legal_moves().unwrap().iter().map(words).collect();
+        assert_eq!(found, HashSet::from(["cat".to_string()]));
+
+        language.select_lexicons(&["small", "other"]).unwrap();
+        let mut game = make_game_in(language);
+        game.players[0].hand = hand("cat");
+        let found: HashSet<_> = game.legal_moves().unwrap().iter().map(words).collect();
+        let expected = ["cat", "act", "ta"].map(String::from);
+        assert_eq!(found, HashSet::from(expected));
+    }
+}
@@ -0,0 +1,63 @@
+//! The one form letters and words are compared in. Tiles, word lists and played words all go
+//! through here, so it doesn't matter which case or which Unicode encoding they were written in.
+//!
+//! The build script includes this file as well, so keep it free of the rest of the crate.
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Case folds `text` and composes it to NFC, so "É", "é" and "e\u{301}" all become "é"
+pub fn normalize(text: &str) -> String {
+    let composed: String = text.nfc().collect();
+    // folding can decompose letters again, like the Turkish "İ"
+    caseless::default_case_fold_str(&composed).nfc().collect()
+}
+
+/// Reads the rules of a language for letters that are written differently but played with the
+/// same tile, like `á:a é:e` for the accented vowels of Spanish. Each rule replaces the letters
+/// before the colon with the ones after it.
+pub fn parse_folding(rules: &str) -> Result<Vec<(String, String)>, String> {
+    rules
+        .split_whitespace()
+        .map(|rule| match rule.split_once(':') {
+            Some((from, to)) if !from.is_empty() => Ok((normalize(from), normalize(to))),
+            _ => Err(format!("expected letter:replacement, not {rule}")),
+        })
+        .collect()
+}
+
+/// Normalizes `text` and then applies the `folding` rules of a language to it
+pub fn fold(text: &str, folding: &[(String, String)]) -> String {
+    folding
+        .iter()
+        .fold(normalize(text), |text, (from, to)| text.replace(from, to))
+}
+
+/// Reads the `alphabet` of a language's metadata into its normalized letters. The letters are
+/// separated by spaces when some tiles have more than one, like `a b c ch d`, and are single
+/// characters otherwise.
+pub fn parse_alphabet(alphabet: &str) -> Vec<String> {
+    let alphabet = normalize(alphabet);
+    match alphabet.contains(char::is_whitespace) {
+        true => alphabet.split_whitespace().map(String::from).collect(),
+        false => alphabet.chars().map(String::from).collect(),
+    }
+}
+
+/// Whether `word` can be split into `letters` at all, without listing every way
+pub fn can_spell(word: &str, letters: &[impl AsRef<str>]) -> bool {
+    // `spellable[i]` is whether the first `i` bytes can be spelled
+    let mut spellable = vec![false; word.len() + 1];
+    spellable[0] = true;
+    for start in 0..word.len() {
+        if !spellable[start] {
+            continue;
+        }
+        for letter in letters {
+            let letter = letter.as_ref();
+            if word[start..].starts_with(letter) {
+                spellable[start + letter.len()] = true;
+            }
+        }
+    }
+    spellable[word.len()]
+}
@@ -0,0 +1,102 @@
+use std::str::FromStr;
+
+use crate::CrabbleError;
+
+/// The house rules a game is played with
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rules {
+    /// How the words formed by a play are checked against the dictionary
+    pub validation: Validation,
+    /// Tiles can only be exchanged while at least this many tiles are left in the bag
+    pub exchange_min_bag: usize,
+    /// When the game ends because nobody manages to score anymore
+    pub scoreless_limit: ScorelessLimit,
+    /// Extra points for playing a whole rack in a single turn
+    pub bingo_bonus: isize,
+    /// Whether players can ask `Game::hints` for moves
+    pub hints: HintPolicy,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            validation: Validation::Auto,
+            exchange_min_bag: 7,
+            scoreless_limit: ScorelessLimit::Turns(6),
+            bingo_bonus: 50,
+            hints: HintPolicy::Unlimited,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Validation {
+    /// Words are never checked, anything that fits on the board is accepted
+    Off,
+    /// `end_turn` refuses plays that form words which are not in the dictionary, the same words
+    /// a challenge would withdraw
+    Auto,
+    /// Plays are accepted as-is, but the next player may challenge them
+    Challenge(ChallengeRule),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HintPolicy {
+    /// No hints, like in rated games
+    Off,
+    /// Every player can ask for hints this many times per game
+    Limited(usize),
+    Unlimited,
+}
+
+/// Reads `off`, `unlimited` or the number of hints per player
+impl FromStr for HintPolicy {
+    type Err = CrabbleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(HintPolicy::Off),
+            "unlimited" => Ok(HintPolicy::Unlimited),
+            _ => s
+                .parse()
+                .map(HintPolicy::Limited)
+                .map_err(|_| CrabbleError::InvalidHintPolicy(s.into())),
+        }
+    }
+}
+
+/// The number of scoreless turns in a row (passes, exchanges, withdrawn plays and turns lost to a
+/// challenge) after which the game is over
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScorelessLimit {
+    /// This many turns, regardless of the number of players
+    Turns(usize),
+    /// This many full rounds, so every player has had as many scoreless turns
+    Rounds(usize),
+}
+
+/// What happens when a play gets challenged
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChallengeRule {
+    /// An invalid play is withdrawn, a failed challenge costs the challenger their turn
+    Double,
+    /// An invalid play is withdrawn, a failed challenge costs nothing
+    Single,
+    /// An invalid play is withdrawn, a failed challenge costs the challenger this many points
+    Penalty(isize),
+}
+
+/// The result of `Game::challenge`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChallengeOutcome {
+    /// The play formed these invalid words and has been taken back
+    Withdrawn(Vec<String>),
+    /// The play was valid and stays on the board
+    Upheld,
+}
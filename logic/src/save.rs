@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{game::Game, CrabbleError};
+
+/// The version of the JSON layout written by `Game::to_json`. Bump this when the layout of any of
+/// the saved types changes after a release, so saves written by that release are refused instead
+/// of misread.
+pub const SAVE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct SavedGame<G> {
+    version: u32,
+    game: G,
+}
+
+/// Only reads the version, so saves with an unknown layout can still be recognized
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+impl Game {
+    /// Saves the game as JSON, including the order of the tiles left in the bag.
+    /// Undo history is not saved.
+    pub fn to_json(&self) -> Result<String, CrabbleError> {
+        serde_json::to_string(&SavedGame {
+            version: SAVE_VERSION,
+            game: self,
+        })
+        .map_err(|e| CrabbleError::InvalidSave(e.to_string()))
+    }
+
+    /// Loads a game saved by `to_json`. Saves in which the player to move does not exist, or
+    /// whose tiles don't add up to those of the language, are refused.
+    pub fn from_json(json: &str) -> Result<Game, CrabbleError> {
+        let header: SaveHeader =
+            serde_json::from_str(json).map_err(|e| CrabbleError::InvalidSave(e.to_string()))?;
+        if header.version != SAVE_VERSION {
+            return Err(CrabbleError::UnsupportedSaveVersion(header.version));
+        }
+
+        let saved: SavedGame<Game> =
+            serde_json::from_str(json).map_err(|e| CrabbleError::InvalidSave(e.to_string()))?;
+        saved
+            .game
+            .check_consistency()
+            .map_err(CrabbleError::InvalidSave)?;
+        Ok(saved.game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::GameState, test_utils::*};
+
+    use super::*;
+
+    #[test]
+    fn json_round_trip() {
+        let mut game = make_game();
+        run_rigged("77hc*at", &mut game).unwrap();
+
+        let mut loaded = Game::from_json(&game.to_json().unwrap()).unwrap();
+        assert_eq!(loaded.seed(), game.seed());
+        assert_eq!(loaded.board(), game.board());
+        assert_eq!(loaded.players, game.players);
+        assert_eq!(loaded.state, game.state);
+        assert_eq!(loaded.history(), game.history());
+        let lexicons = |game: &Game| {
+            let selected = game.language().selected_lexicons();
+            selected
+                .map(|lexicon| lexicon.name().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lexicons(&loaded), lexicons(&game));
+
+        // the bag keeps its order, so both games draw the same tiles
+        let tiles = game.players[1].hand.tiles()[..4].to_vec();
+        game.exchange(&tiles).unwrap();
+        loaded.exchange(&tiles).unwrap();
+        assert_eq!(loaded.players[1].hand, game.players[1].hand);
+    }
+
+    #[test]
+    fn unknown_version_is_refused() {
+        let json = make_game().to_json().unwrap();
+        let json = json.replacen(&format!("\"version\":{SAVE_VERSION}"), "\"version\":999", 1);
+
+        assert_eq!(
+            Game::from_json(&json).unwrap_err(),
+            CrabbleError::UnsupportedSaveVersion(999)
+        );
+    }
+
+    #[test]
+    fn corrupted_saves_are_refused() {
+        let mut game = make_game();
+        run_rigged("77hc*at", &mut game).unwrap();
+
+        let mut missing_player = game.clone();
+        missing_player.state = GameState::Turn(2, false);
+        let json = missing_player.to_json().unwrap();
+        assert!(matches!(
+            Game::from_json(&json),
+            Err(CrabbleError::InvalidSave(_))
+        ));
+
+        // there is only one "z" in English
+        let mut extra_tiles = game.clone();
+        extra_tiles.players[0].hand = hand("zzzzzzz");
+        let json = extra_tiles.to_json().unwrap();
+        assert!(matches!(
+            Game::from_json(&json),
+            Err(CrabbleError::InvalidSave(_))
+        ));
+
+        assert!(Game::from_json(&game.to_json().unwrap()).is_ok());
+    }
+}
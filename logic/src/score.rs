@@ -0,0 +1,89 @@
+use crate::{Coordinate, Tile};
+
+/// How the score of a play came about
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoreBreakdown {
+    /// The word along the line the tiles were played in
+    pub main_word: WordScore,
+    /// The words the play formed across that line
+    pub cross_words: Vec<WordScore>,
+    /// The bonus for playing a whole rack at once, if the play earned it
+    pub bingo_bonus: Option<isize>,
+}
+
+impl ScoreBreakdown {
+    /// Every scored word, the main word first
+    pub fn words(&self) -> impl Iterator<Item = &WordScore> {
+        std::iter::once(&self.main_word).chain(&self.cross_words)
+    }
+
+    /// The squares of the jokers in the scored words, which counted as zero
+    pub fn jokers(&self) -> Vec<Coordinate> {
+        let mut jokers: Vec<Coordinate> = Vec::new();
+        for letter in self.words().flat_map(|word| &word.letters) {
+            if letter.tile.is_joker() && !jokers.contains(&letter.coordinate) {
+                jokers.push(letter.coordinate);
+            }
+        }
+        jokers
+    }
+
+    pub fn total(&self) -> isize {
+        self.words().map(WordScore::total).sum::<isize>() + self.bingo_bonus.unwrap_or(0)
+    }
+}
+
+/// The points for a single word of a play
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WordScore {
+    pub word: String,
+    pub letters: Vec<LetterScore>,
+    /// The product of the word multipliers that applied to the word
+    pub word_multiplier: isize,
+}
+
+impl WordScore {
+    pub fn total(&self) -> isize {
+        self.letters.iter().map(LetterScore::total).sum::<isize>() * self.word_multiplier
+    }
+}
+
+/// The points for a single tile of a word
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LetterScore {
+    pub coordinate: Coordinate,
+    pub tile: Tile,
+    /// Whether the tile was placed by this play, rather than already on the board
+    pub placed: bool,
+    /// What the tile is worth by itself, which is zero for a joker
+    pub value: isize,
+    /// The letter multiplier that applied to the tile, 1 if there was none
+    pub letter_multiplier: isize,
+}
+
+impl LetterScore {
+    pub fn total(&self) -> isize {
+        self.value * self.letter_multiplier
+    }
+}
+
+/// A player's result once the game is over
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Standing {
+    /// Position in the final ranking, starting at 1. Tied players share a rank.
+    pub rank: usize,
+    /// Index of the player in `Game::players`
+    pub player: usize,
+    pub name: String,
+    /// The score from the turns that were played
+    pub play_score: isize,
+    /// The value of the tiles left on the player's rack, which is subtracted
+    pub leftover_penalty: isize,
+    /// The value of everyone else's leftover tiles, for the player who went out
+    pub going_out_bonus: isize,
+    pub final_score: isize,
+}
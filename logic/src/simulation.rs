@@ -0,0 +1,269 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    game::Game,
+    movegen::Move,
+    strategy::{Greedy, Strategy},
+    CrabbleError,
+};
+
+/// How long a simulation may run
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Budget {
+    /// Play out this many futures for every candidate
+    Iterations(usize),
+    /// Keep playing out futures until this much time has passed. At least one is always played.
+    Time(Duration),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// How many of the highest scoring moves are simulated
+    pub candidates: usize,
+    pub budget: Budget,
+    pub threads: usize,
+    /// How many points of spread a certain win is worth when ranking the candidates
+    pub win_weight: f64,
+    /// Where the random racks come from, so a simulation can be repeated
+    pub seed: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            candidates: 10,
+            budget: Budget::Iterations(100),
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+            win_weight: 50.0,
+            seed: 0,
+        }
+    }
+}
+
+/// How a candidate move did in the simulation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Evaluation {
+    pub play: Move,
+    /// The number of futures that were played out
+    pub iterations: usize,
+    /// The average final lead over the best opponent, once the leftover tiles are settled
+    pub spread: f64,
+    /// The share of the futures the player won, counting ties as half
+    pub win_probability: f64,
+    /// What the candidates are ranked by: the spread plus the win probability times the
+    /// `win_weight` of the settings
+    pub equity: f64,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Totals {
+    iterations: usize,
+    spread: isize,
+    /// Counted in halves, so ties stay exact
+    half_wins: usize,
+}
+
+/// Plays the best candidate moves of the current player of `game` against random racks for the
+/// opponents, drawn from the tiles the player can't see, and ranks them best first.
+///
+/// Every future is played to the end of the game by greedy players, and is the same for all the
+/// candidates, so they are compared on equal terms. Futures are shorter the fewer tiles are left,
+/// so late in the game the same budget goes further. With `Budget::Iterations` the results only depend on the settings, not
+/// on the number of threads. With `Budget::Time` they don't: how many futures fit in the time
+/// depends on the threads and the machine.
+pub fn simulate(game: &Game, settings: &Settings) -> Result<Vec<Evaluation>, CrabbleError> {
+    let player = game.current_player().ok_or(CrabbleError::GameOver)?;
+    let mut candidates = game.legal_moves()?;
+    candidates.truncate(settings.candidates);
+
+    let next = AtomicUsize::new(0);
+    let start = Instant::now();
+    let totals = Mutex::new(vec![Totals::default(); candidates.len()]);
+
+    thread::scope(|scope| {
+        for _ in 0..settings.threads.max(1) {
+            scope.spawn(|| {
+                let mut local = vec![Totals::default(); candidates.len()];
+                loop {
+                    let iteration = next.fetch_add(1, Ordering::Relaxed);
+                    let done = match settings.budget {
+                        Budget::Iterations(max) => iteration >= max,
+                        Budget::Time(limit) => iteration > 0 && start.elapsed() >= limit,
+                    };
+                    if done {
+                        break;
+                    }
+
+                    let seed = settings.seed.wrapping_add(iteration as u64);
+                    let future = game.imagine(player, &mut ChaCha8Rng::seed_from_u64(seed));
+                    for (play, totals) in candidates.iter().zip(&mut local) {
+                        if let Some(spread) = play_out(&future, player, play) {
+                            totals.iterations += 1;
+                            totals.spread += spread;
+                            totals.half_wins += (1 + spread.signum()) as usize;
+                        }
+                    }
+                }
+
+                let mut totals = totals.lock().unwrap();
+                for (total, local) in totals.iter_mut().zip(local) {
+                    total.iterations += local.iterations;
+                    total.spread += local.spread;
+                    total.half_wins += local.half_wins;
+                }
+            });
+        }
+    });
+
+    let mut evaluations: Vec<Evaluation> = candidates
+        .into_iter()
+        .zip(totals.into_inner().unwrap())
+        .map(|(play, totals)| {
+            let iterations = totals.iterations.max(1) as f64;
+            let spread = totals.spread as f64 / iterations;
+            let win_probability = totals.half_wins as f64 / 2.0 / iterations;
+            Evaluation {
+                play,
+                iterations: totals.iterations,
+                spread,
+                win_probability,
+                equity: spread + win_probability * settings.win_weight,
+            }
+        })
+        .collect();
+    evaluations.sort_by(|a, b| b.equity.total_cmp(&a.equity));
+
+    Ok(evaluations)
+}
+
+/// Plays `play` and then greedy turns until the game is over, and returns how far `player` ends
+/// up ahead of the best of the others, with the leftover tiles settled
+fn play_out(future: &Game, player: usize, play: &Move) -> Option<isize> {
+    let mut game = future.clone();
+    game.play_move(play).ok()?;
+
+    while game.current_player().is_some() {
+        let choice = Greedy.choose(&game);
+        game.play_choice(&choice).ok()?;
+    }
+
+    let score = game.players[player].score();
+    let best_other = game
+        .players
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != player)
+        .map(|(_, other)| other.score())
+        .max()
+        .unwrap_or(0);
+    Some(score - best_other)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::*, HandTile};
+
+    use super::*;
+
+    /// A seeded game with only a few tiles left in the bag, so the futures are short
+    fn late_game() -> Game {
+        let mut game = seeded_game(11);
+        game.drain_bag(5);
+        game
+    }
+
+    fn settings(budget: Budget, threads: usize) -> Settings {
+        Settings {
+            candidates: 3,
+            budget,
+            threads,
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn candidates_are_ranked_by_equity() {
+        let game = late_game();
+        let best: Vec<Move> = game.legal_moves().unwrap().into_iter().take(3).collect();
+
+        let evaluations = simulate(&game, &settings(Budget::Iterations(4), 2)).unwrap();
+        assert_eq!(evaluations.len(), 3);
+        for evaluation in &evaluations {
+            assert!(best.contains(&evaluation.play));
+            assert_eq!(evaluation.iterations, 4);
+            assert!((0.0..=1.0).contains(&evaluation.win_probability));
+        }
+        assert!(evaluations.windows(2).all(|w| w[0].equity >= w[1].equity));
+
+        // the threads only share out the work
+        let alone = simulate(&game, &settings(Budget::Iterations(4), 1)).unwrap();
+        assert_eq!(alone, evaluations);
+    }
+
+    #[test]
+    fn time_budget_stops_the_simulation() {
+        let game = late_game();
+        let start = Instant::now();
+        let evaluations =
+            simulate(&game, &settings(Budget::Time(Duration::from_millis(50)), 2)).unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(evaluations
+            .iter()
+            .all(|evaluation| evaluation.iterations > 0));
+    }
+
+    #[test]
+    fn futures_are_played_to_the_end() {
+        // player 2 goes out with the "s" whatever they play, and catches the "q" left on the
+        // other rack
+        let mut game = make_game();
+        run_rigged("77hcat", &mut game).unwrap();
+        game.drain_bag(0);
+        game.players[0].hand = hand("q");
+        game.players[1].hand = hand("s");
+
+        let behind = game.players[1].score() as f64 - game.players[0].score() as f64;
+        let evaluations = simulate(&game, &settings(Budget::Iterations(2), 1)).unwrap();
+        assert!(!evaluations.is_empty());
+        for evaluation in &evaluations {
+            assert_eq!(evaluation.win_probability, 1.0);
+            assert_eq!(
+                evaluation.spread,
+                behind + evaluation.play.score() as f64 + 2.0 * 10.0
+            );
+        }
+    }
+
+    #[test]
+    fn futures_only_change_hidden_tiles() {
+        let game = seeded_game(11);
+        let sorted = |tiles: &[HandTile]| {
+            let mut tiles = tiles.to_vec();
+            tiles.sort_by_key(|tile| tile.to_string());
+            tiles
+        };
+
+        let future = game.imagine(0, &mut ChaCha8Rng::seed_from_u64(3));
+        assert_eq!(future.players[0].hand, game.players[0].hand);
+        assert_eq!(future.tiles_in_bag(), game.tiles_in_bag());
+        assert_eq!(
+            future.players[1].hand.tiles().len(),
+            game.players[1].hand.tiles().len()
+        );
+        assert_ne!(
+            sorted(future.players[1].hand.tiles()),
+            sorted(game.players[1].hand.tiles())
+        );
+    }
+}
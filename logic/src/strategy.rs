@@ -0,0 +1,368 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    dictionary::Dictionary,
+    game::Game,
+    language::{Language, Lexicon, COMMON_LEXICON},
+    movegen::Move,
+    CrabbleError, HandTile, Letter,
+};
+
+/// What a strategy decided to do with its turn
+#[derive(Clone, Debug, PartialEq)]
+pub enum Choice {
+    Play(Move),
+    Exchange(Vec<HandTile>),
+    Pass,
+}
+
+/// Chooses the turns of a computer player
+pub trait Strategy {
+    /// Picks a turn for the current player of `game`
+    fn choose(&self, game: &Game) -> Choice;
+}
+
+/// The computer players a seat can be given, from the weakest to the strongest
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Bot {
+    Beginner,
+    Greedy,
+    Strong,
+}
+
+impl Bot {
+    pub const ALL: [Bot; 3] = [Bot::Beginner, Bot::Greedy, Bot::Strong];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Bot::Beginner => "beginner",
+            Bot::Greedy => "greedy",
+            Bot::Strong => "strong",
+        }
+    }
+
+    /// The strategy this bot plays with in a game in `language`
+    pub fn strategy(self, language: &Language) -> Box<dyn Strategy> {
+        match self {
+            Bot::Beginner => Box::new(Beginner::for_language(language)),
+            Bot::Greedy => Box::new(Greedy),
+            Bot::Strong => Box::new(RackLeave),
+        }
+    }
+}
+
+impl Display for Bot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Bot {
+    type Err = CrabbleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Bot::ALL
+            .into_iter()
+            .find(|bot| bot.name() == s)
+            .ok_or_else(|| CrabbleError::UnknownBot(s.into()))
+    }
+}
+
+/// Always plays the highest scoring move
+pub struct Greedy;
+
+impl Strategy for Greedy {
+    fn choose(&self, game: &Game) -> Choice {
+        match game.legal_moves() {
+            Ok(moves) if !moves.is_empty() => Choice::Play(moves[0].clone()),
+            _ => no_play(game),
+        }
+    }
+}
+
+/// Plays the highest scoring move that only forms words from a small vocabulary, the way someone
+/// new to the game would
+pub struct Beginner {
+    vocabulary: Option<Lexicon>,
+}
+
+impl Beginner {
+    /// Words no longer than this are played when there is no vocabulary
+    const MAX_WORD_LENGTH: usize = 4;
+
+    /// Plays words from `vocabulary` that are also valid in the game
+    pub fn new(vocabulary: Lexicon) -> Self {
+        Beginner {
+            vocabulary: Some(vocabulary),
+        }
+    }
+
+    /// Plays words from the common edition of the lexicon of `language`, or short words if it
+    /// doesn't have one
+    pub fn for_language(language: &Language) -> Self {
+        Beginner {
+            vocabulary: language
+                .lexicons
+                .iter()
+                .find(|lexicon| lexicon.name() == COMMON_LEXICON)
+                .cloned(),
+        }
+    }
+
+    fn knows(&self, word: &str) -> bool {
+        match &self.vocabulary {
+            Some(vocabulary) => vocabulary.contains(word),
+            None => word.chars().count() <= Self::MAX_WORD_LENGTH,
+        }
+    }
+}
+
+impl Strategy for Beginner {
+    fn choose(&self, game: &Game) -> Choice {
+        let moves = game.legal_moves().unwrap_or_default();
+        match moves
+            .into_iter()
+            .find(|play| play.words.iter().all(|word| self.knows(word)))
+        {
+            Some(play) => Choice::Play(play),
+            None => no_play(game),
+        }
+    }
+}
+
+/// Weighs the score of a move against the tiles it leaves on the rack, and exchanges when the
+/// rack is bad enough
+pub struct RackLeave;
+
+impl RackLeave {
+    /// A rough guess of how many points the tiles in `leave` are worth in the turns to come
+    pub fn leave_value(leave: &[HandTile], language: &Language) -> f32 {
+        let mut value = 0.0;
+        let mut vowels = 0;
+        let mut consonants = 0;
+        let mut seen: Vec<Letter> = Vec::new();
+
+        for tile in leave {
+            match tile {
+                HandTile::Joker => value += 8.0,
+                HandTile::Letter(letter) => {
+                    // duplicates make words harder to find
+                    if seen.contains(letter) {
+                        value -= 3.0;
+                    }
+                    seen.push(*letter);
+
+                    // and so do the rare letters that are worth a lot
+                    let points = language.values.value(*tile) as f32;
+                    value -= (points - 4.0).max(0.0) / 2.0;
+
+                    match is_vowel(*letter) {
+                        true => vowels += 1,
+                        false => consonants += 1,
+                    }
+                }
+            }
+        }
+
+        let imbalance = (vowels - consonants as isize).abs() - 1;
+        value - 2.0 * imbalance.max(0) as f32
+    }
+
+    fn equity(play: &Move, game: &Game, rack: &[HandTile]) -> f32 {
+        let score = play.score() as f32;
+        if game.tiles_in_bag() == 0 {
+            return score;
+        }
+
+        score + Self::leave_value(&play.leave(rack), game.language())
+    }
+
+    /// The tiles to exchange to keep the best leave, with the value of that leave
+    fn best_exchange(game: &Game, rack: &[HandTile]) -> Option<(Vec<HandTile>, f32)> {
+        let mut best: Option<(Vec<HandTile>, f32)> = None;
+
+        // every way of keeping some of the tiles, but not all of them
+        for keep in 0..(1u32 << rack.len()) - 1 {
+            let (kept, exchanged): (Vec<_>, Vec<_>) = rack
+                .iter()
+                .enumerate()
+                .partition(|(i, _)| keep & (1 << i) != 0);
+            let exchanged: Vec<HandTile> = exchanged.into_iter().map(|(_, t)| *t).collect();
+            if !game.can_exchange(exchanged.len()) {
+                continue;
+            }
+
+            let kept: Vec<HandTile> = kept.into_iter().map(|(_, t)| *t).collect();
+            let value = Self::leave_value(&kept, game.language());
+            if best.as_ref().is_none_or(|(_, best)| value > *best) {
+                best = Some((exchanged, value));
+            }
+        }
+
+        best
+    }
+}
+
+impl Strategy for RackLeave {
+    fn choose(&self, game: &Game) -> Choice {
+        let Some(player) = game.current_player() else {
+            return Choice::Pass;
+        };
+        let rack = game.players[player].hand.tiles();
+
+        let best_play = game
+            .legal_moves()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|play| {
+                let equity = Self::equity(&play, game, rack);
+                (play, equity)
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        match (best_play, Self::best_exchange(game, rack)) {
+            (Some((_, play)), Some((tiles, exchange))) if exchange > play => {
+                Choice::Exchange(tiles)
+            }
+            (Some((play, _)), _) => Choice::Play(play),
+            (None, Some((tiles, _))) => Choice::Exchange(tiles),
+            (None, None) => Choice::Pass,
+        }
+    }
+}
+
+/// What to do without a move to play: swap the whole rack if the bag allows it, or pass
+fn no_play(game: &Game) -> Choice {
+    let Some(player) = game.current_player() else {
+        return Choice::Pass;
+    };
+    let rack = game.players[player].hand.tiles();
+
+    match !rack.is_empty() && game.can_exchange(rack.len()) {
+        true => Choice::Exchange(rack.to_vec()),
+        false => Choice::Pass,
+    }
+}
+
+/// Tiles with more than one letter count as vowels if any of their letters is one, like the IJ
+fn is_vowel(letter: Letter) -> bool {
+    letter
+        .chars()
+        .any(|letter| "aeiouàáâäèéêëìíîïòóôöùúûü".contains(letter))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game::{GameState, Player},
+        rules::Rules,
+        standard_board_layout,
+        test_utils::hand,
+        BoardLayout,
+    };
+
+    use super::*;
+
+    fn bot_game(seed: u64, bots: [Bot; 2]) -> Game {
+        let layout = BoardLayout::from_fn((15, 15), standard_board_layout);
+        let players = bots
+            .iter()
+            .enumerate()
+            .map(|(i, bot)| Player::computer(format!("Bot {}", i + 1), *bot))
+            .collect();
+        Game::seeded(
+            players,
+            layout,
+            Language::by_name("english").unwrap(),
+            Rules::default(),
+            seed,
+        )
+    }
+
+    #[test]
+    fn bots_finish_a_game() {
+        let mut game = bot_game(7, [Bot::Greedy, Bot::Strong]);
+        let mut turns = 0;
+        while game.state != GameState::Done {
+            game.play_bot_turn().unwrap();
+            turns += 1;
+            assert!(turns < 100);
+        }
+
+        assert!(game.players.iter().all(|player| player.score() > 0));
+    }
+
+    #[test]
+    fn greedy_plays_the_best_move() {
+        let mut game = bot_game(1, [Bot::Greedy, Bot::Greedy]);
+        game.players[0].hand = hand("quizabc");
+        let best = game.legal_moves().unwrap()[0].clone();
+
+        assert_eq!(Greedy.choose(&game), Choice::Play(best.clone()));
+        game.play_bot_turn().unwrap();
+        assert_eq!(game.history()[0].score, best.score());
+    }
+
+    #[test]
+    fn beginner_sticks_to_common_words() {
+        let mut game = bot_game(3, [Bot::Beginner, Bot::Beginner]);
+        let common = Lexicon::find("english", COMMON_LEXICON).unwrap();
+
+        for _ in 0..6 {
+            let Choice::Play(play) = Bot::Beginner.strategy(game.language()).choose(&game) else {
+                game.play_bot_turn().unwrap();
+                continue;
+            };
+            assert!(
+                play.words.iter().all(|word| common.contains(word)),
+                "{play:?}"
+            );
+            game.play_bot_turn().unwrap();
+        }
+
+        let short = Beginner { vocabulary: None };
+        assert!(short.knows("quiz"));
+        assert!(!short.knows("quizzed"));
+    }
+
+    #[test]
+    fn rack_leave_values() {
+        let english = Language::by_name("english").unwrap();
+        let value = |s: &str| RackLeave::leave_value(hand(s).tiles(), &english);
+
+        assert!(value("er*") > value("er"));
+        assert!(value("ert") > value("eee"));
+        assert!(value("ert") > value("rtv"));
+        assert!(value("ert") > value("erq"));
+
+        // a rack like this is better swapped than played
+        let mut game = bot_game(5, [Bot::Strong, Bot::Strong]);
+        game.players[0].hand = hand("uuuuiii");
+        match RackLeave.choose(&game) {
+            Choice::Exchange(tiles) => assert!(tiles.len() >= 4),
+            choice => panic!("{choice:?}"),
+        }
+    }
+
+    #[test]
+    fn humans_are_not_played_for() {
+        let layout = BoardLayout::from_fn((15, 15), standard_board_layout);
+        let players = vec![
+            Player::new("Human".to_string()),
+            Player::computer("Bot".to_string(), Bot::Greedy),
+        ];
+        let mut game = Game::new(players, layout, Language::by_name("english").unwrap());
+
+        assert_eq!(game.play_bot_turn().unwrap_err(), CrabbleError::NotABot);
+        game.pass().unwrap();
+        game.play_bot_turn().unwrap();
+        assert_eq!(game.current_player(), Some(0));
+
+        assert_eq!("strong".parse(), Ok(Bot::Strong));
+        assert_eq!(
+            "expert".parse::<Bot>(),
+            Err(CrabbleError::UnknownBot("expert".into()))
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Games and racks the tests of the crate start from
+
+use std::str::FromStr;
+
+use crate::{
+    asn::ASN,
+    game::{Game, Player},
+    language::Language,
+    rules::Rules,
+    BoardLayout, CrabbleError, Hand, HandTile,
+};
+
+/// The two human players of a test game
+pub fn players() -> Vec<Player> {
+    vec![
+        Player::new("Gamer 1".to_string()),
+        Player::new("Player 2".to_string()),
+    ]
+}
+
+/// An English game on the standard board
+pub fn make_game() -> Game {
+    make_game_with_rules(Rules::default())
+}
+
+pub fn make_game_with_rules(rules: Rules) -> Game {
+    Game::with_rules(
+        players(),
+        BoardLayout::standard(),
+        Language::by_name("english").unwrap(),
+        rules,
+    )
+}
+
+/// An English game on the standard board that deals from `seed`, see `Game::seeded`
+pub fn seeded_game(seed: u64) -> Game {
+    Game::seeded(
+        players(),
+        BoardLayout::standard(),
+        Language::by_name("english").unwrap(),
+        Rules::default(),
+        seed,
+    )
+}
+
+/// A game in `language` on the standard board
+pub fn make_game_in(language: Language) -> Game {
+    Game::new(players(), BoardLayout::standard(), language)
+}
+
+/// A rack with the letters of `s`, where `*` is a joker
+pub fn hand(s: &str) -> Hand {
+    Hand::from(
+        s.chars()
+            .map(|c| match c {
+                '*' => HandTile::Joker,
+                c => HandTile::Letter(c.into()),
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Plays `asn` one line at a time, first handing the player to move exactly the tiles of their
+/// line, since the racks in a fresh game are random
+pub fn run_rigged(asn: &str, game: &mut Game) -> Result<(), CrabbleError> {
+    for line in ASN::from_str(asn).unwrap().lines {
+        let player = game.current_player().ok_or(CrabbleError::GameOver)?;
+        let rack: Vec<HandTile> = line.tiles.iter().map(|t| HandTile::from(*t)).collect();
+        game.rig_rack(player, &rack);
+        ASN { lines: vec![line] }.run(game, false)?;
+    }
+    Ok(())
+}
@@ -1,18 +1,30 @@
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
-use axum::Router;
-use logic::Board;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use logic::language::{self, LanguageEntry};
+use logic::strategy::Bot;
+use logic::{Board, BoardLayout};
+use serde::Deserialize;
 use store::Store;
 use uuid::Uuid;
 
 mod store;
 
+// the names and bots are only read once games are played on the server
+#[allow(dead_code)]
 #[derive(Clone)]
 struct Player {
     name: String,
     id: Uuid,
+    bot: Option<Bot>, // Set when the computer plays this seat
 }
 
+// games can't be started on the server yet
+#[allow(dead_code)]
 #[derive(Clone, Copy)]
 enum GameState {
     Pending,
@@ -24,7 +36,10 @@ enum GameState {
 struct Game {
     uuid: Uuid,           // We're storing the Uuids de-normalized cause it makes it easier lol
     players: Vec<Player>, // Order determines turn order
+    // nothing is played on the server yet
+    #[allow(dead_code)]
     board: Board,
+    #[allow(dead_code)]
     state: GameState,
 }
 
@@ -32,14 +47,95 @@ struct Game {
 async fn main() {
     tracing_subscriber::fmt::init();
 
-    let store = HashMap::<Uuid, Game>::new();
+    // languages in this directory can be played next to the bundled ones
+    if let Ok(dir) = std::env::var("CRABBLE_LANGUAGES") {
+        match language::load_languages(&dir) {
+            Ok(loaded) => tracing::info!("loaded languages: {}", loaded.join(", ")),
+            Err(e) => tracing::error!(
+                "could not load the languages in {dir}, only the bundled ones can be played: {e}"
+            ),
+        }
+    }
 
-    let app = Router::new(); // add routes
+    let games = Games::default();
+
+    let app = Router::new()
+        .route("/languages", get(languages))
+        .route("/bots", get(bots))
+        .route("/layouts", get(layouts))
+        .route("/games", post(create_game))
+        .route("/games/{game}/bots", post(create_bot))
+        .with_state(games);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
 
+async fn languages() -> Json<Vec<LanguageEntry>> {
+    Json(language::available())
+}
+
+async fn bots() -> Json<Vec<&'static str>> {
+    Json(Bot::ALL.map(Bot::name).to_vec())
+}
+
+async fn layouts() -> Json<Vec<&'static str>> {
+    Json(BoardLayout::available())
+}
+
+/// The games of this server, by id
+type Games = Arc<Mutex<HashMap<Uuid, Game>>>;
+
+/// Creates a pending game and returns its id
+async fn create_game(State(games): State<Games>) -> Result<Json<String>, (StatusCode, String)> {
+    let uuid = start_game(&mut *games.lock().unwrap()).map_err(|()| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "The game could not be stored".to_string(),
+        )
+    })?;
+    Ok(Json(uuid.to_string()))
+}
+
+#[derive(Deserialize)]
+struct AddBot {
+    /// One of the names `/bots` lists
+    bot: String,
+}
+
+/// Gives the next seat of a game to a computer player, and returns the id of that seat
+async fn create_bot(
+    State(games): State<Games>,
+    Path(game): Path<String>,
+    Json(body): Json<AddBot>,
+) -> Result<Json<String>, (StatusCode, String)> {
+    let not_found = || (StatusCode::NOT_FOUND, format!("There is no game {game}"));
+    let uuid = Uuid::parse_str(&game).map_err(|_| not_found())?;
+    let bot = body
+        .bot
+        .parse::<Bot>()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let id = add_bot(&mut *games.lock().unwrap(), uuid, bot).map_err(|e| match e {
+        SeatError::Store(()) => not_found(),
+        SeatError::Full => (
+            StatusCode::CONFLICT,
+            format!("Game {game} already has {MAX_SEATS} players"),
+        ),
+    })?;
+    Ok(Json(id.to_string()))
+}
+
+/// As many players as a game of the logic crate takes
+const MAX_SEATS: usize = 4;
+
+/// Why a seat could not be added to a game
+enum SeatError<E> {
+    Store(E),
+    /// The game already has `MAX_SEATS` players
+    Full,
+}
+
 fn start_game<S: Store>(store: &mut S) -> Result<Uuid, S::Error> {
     let uuid = Uuid::new_v4();
 
@@ -54,3 +150,69 @@ fn start_game<S: Store>(store: &mut S) -> Result<Uuid, S::Error> {
 
     Ok(uuid)
 }
+
+fn add_bot<S: Store>(store: &mut S, game: Uuid, bot: Bot) -> Result<Uuid, SeatError<S::Error>> {
+    let mut game = store.load_game(game).map_err(SeatError::Store)?;
+    if game.players.len() >= MAX_SEATS {
+        return Err(SeatError::Full);
+    }
+    let id = Uuid::new_v4();
+
+    game.players.push(Player {
+        name: bot.to_string(),
+        id,
+        bot: Some(bot),
+    });
+    store.save_game(game.uuid, game).map_err(SeatError::Store)?;
+
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bot(name: &str) -> Json<AddBot> {
+        Json(AddBot { bot: name.into() })
+    }
+
+    #[tokio::test]
+    async fn bots_take_seats() {
+        let games = Games::default();
+        let Json(game) = create_game(State(games.clone())).await.unwrap();
+
+        let Json(seat) = create_bot(State(games.clone()), Path(game.clone()), bot("greedy"))
+            .await
+            .unwrap();
+        let stored = games.lock().unwrap()[&game.parse().unwrap()].clone();
+        assert_eq!(stored.players.len(), 1);
+        assert_eq!(stored.players[0].id.to_string(), seat);
+        assert_eq!(stored.players[0].bot, Some(Bot::Greedy));
+
+        let (status, _) = create_bot(State(games.clone()), Path(game.clone()), bot("expert"))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = create_bot(
+            State(games.clone()),
+            Path(Uuid::new_v4().to_string()),
+            bot("strong"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // a game takes at most four players
+        for _ in 1..MAX_SEATS {
+            let _seat = create_bot(State(games.clone()), Path(game.clone()), bot("greedy"))
+                .await
+                .unwrap();
+        }
+        let (status, _) = create_bot(State(games.clone()), Path(game.clone()), bot("greedy"))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+        let stored = games.lock().unwrap()[&game.parse().unwrap()].clone();
+        assert_eq!(stored.players.len(), MAX_SEATS);
+    }
+}
@@ -9,6 +9,7 @@ pub trait Store {
 
     fn save_game(&mut self, uuid: Uuid, game: Game) -> Result<(), Self::Error>;
     fn load_game(&self, uuid: Uuid) -> Result<Game, Self::Error>;
+    #[allow(dead_code)] // nothing lists the games yet
     fn get_all_games(&self) -> Result<HashMap<Uuid, Game>, Self::Error>;
 
     // TODO: saving logins?